@@ -0,0 +1,327 @@
+use axum::{
+    Json, Router,
+    extract::{FromRequestParts, Path, State},
+    http::{StatusCode, header::AUTHORIZATION, request::Parts},
+    routing::{get, post},
+};
+use serde::Serialize;
+use tracing::{error, info};
+
+use crate::{
+    AppState, Dispatched, dispatch,
+    store::{DeadLetter, Source},
+};
+
+/// Endpoints for looking after failed events. They're only available when
+/// `ADMIN_TOKEN` is set, and need it as a bearer token.
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/dead-letters", get(list))
+        .route("/dead-letters/{id}", get(inspect))
+        .route("/dead-letters/{id}/replay", post(replay))
+}
+
+/// Proof that the request carried the admin token.
+struct Admin;
+
+impl FromRequestParts<AppState> for Admin {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let Some(token) = &state.admin_token else {
+            return Err(StatusCode::NOT_FOUND);
+        };
+        let provided = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .ok_or(StatusCode::UNAUTHORIZED)?;
+        if constant_time_eq(provided.as_bytes(), token.as_bytes()) {
+            Ok(Self)
+        } else {
+            Err(StatusCode::UNAUTHORIZED)
+        }
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// A dead letter without its payload, for listing.
+#[derive(Serialize)]
+struct Summary {
+    id: i64,
+    event_type: String,
+    delivery_id: Option<String>,
+    destination: Option<String>,
+    error: String,
+    created_at: i64,
+}
+
+async fn list(
+    _: Admin,
+    State(app_state): State<AppState>,
+) -> Result<Json<Vec<Summary>>, StatusCode> {
+    let letters = app_state.queue.store().dead_letters().map_err(|e| {
+        error!(%e, "failed to list dead letters");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(
+        letters
+            .into_iter()
+            .map(|letter| Summary {
+                id: letter.id,
+                event_type: letter.event_type,
+                delivery_id: letter.delivery_id,
+                destination: letter.destination,
+                error: letter.error,
+                created_at: letter.created_at,
+            })
+            .collect(),
+    ))
+}
+
+async fn inspect(
+    _: Admin,
+    State(app_state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<DeadLetter>, StatusCode> {
+    match app_state.queue.store().dead_letter(id) {
+        Ok(Some(letter)) => Ok(Json(letter)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!(%e, id, "failed to read dead letter");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Runs a dead letter through the current routing and rendering again. If it
+/// failed to deliver to one destination, only that destination is retried.
+/// The letter is kept unless the event was queued again, or failed again and
+/// came back as a new dead letter with the new error.
+async fn replay(_: Admin, State(app_state): State<AppState>, Path(id): Path<i64>) -> StatusCode {
+    let store = app_state.queue.store();
+    let letter = match store.dead_letter(id) {
+        Ok(Some(letter)) => letter,
+        Ok(None) => return StatusCode::NOT_FOUND,
+        Err(e) => {
+            error!(%e, id, "failed to read dead letter");
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    };
+
+    info!(id, event_type = letter.event_type, "replaying dead letter");
+    let source = Source {
+        event_type: letter.event_type,
+        delivery_id: letter.delivery_id,
+        payload: letter.payload,
+    };
+    let status = match dispatch(&app_state, &source, letter.destination.as_deref()) {
+        Ok(Dispatched::Queued) => StatusCode::ACCEPTED,
+        Ok(Dispatched::DeadLettered) => StatusCode::UNPROCESSABLE_ENTITY,
+        Ok(Dispatched::Ignored) => {
+            info!(id, "dead letter no longer routes anywhere - keeping it");
+            return StatusCode::CONFLICT;
+        }
+        Err(e) => {
+            error!(%e, id, "failed to replay dead letter");
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    };
+    if let Err(e) = store.remove_dead_letter(id) {
+        error!(%e, id, "failed to remove replayed dead letter");
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{Router, http::StatusCode};
+    use axum_github_webhook_extract::GithubToken;
+    use serde_json::Value;
+
+    use crate::{
+        AppState,
+        config::FileConfig,
+        dedupe::Deliveries,
+        queue::Queue,
+        store::{Source, Store},
+    };
+
+    const TOKEN: &str = "admin-secret";
+
+    /// Serves the admin endpoints on a local port, returning their base url.
+    async fn serve(admin_token: Option<&str>) -> (String, Store) {
+        let config: FileConfig = r#"
+            error_destination = "main"
+            [destinations.main]
+            url = "http://127.0.0.1:9/"
+            [destinations.other]
+            url = "http://127.0.0.1:9/"
+            match = { event = ["release"] }
+            [[rules]]
+            destinations = ["main", "other"]
+        "#
+        .parse()
+        .expect("config is valid");
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        let state = AppState {
+            deliveries: Arc::new(Deliveries::new(config.dedupe.clone(), &store).unwrap()),
+            queue: Queue::new(store.clone(), &config),
+            config: Arc::new(config),
+            admin_token: admin_token.map(Arc::from),
+            github_token: GithubToken(Arc::new("secret".to_string())),
+        };
+        let app = Router::new()
+            .nest("/admin", super::router())
+            .with_state(state);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (format!("http://{addr}/admin"), store)
+    }
+
+    fn dead_letter(store: &Store, destination: &str) -> i64 {
+        dead_letter_of(store, "issue_comment", "created", destination)
+    }
+
+    fn dead_letter_of(store: &Store, event_type: &str, fixture: &str, destination: &str) -> i64 {
+        let payload = std::fs::read_to_string(format!(
+            "{}/fixtures/{event_type}/{fixture}.json",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .expect("fixture exists");
+        let source = Source {
+            event_type: event_type.to_string(),
+            delivery_id: Some("delivery-1".to_string()),
+            payload: serde_json::from_str(&payload).expect("fixture is valid json"),
+        };
+        store
+            .push_dead_letter(&source, Some(destination), "hook failed")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_endpoints_need_the_admin_token() {
+        let client = reqwest::Client::new();
+        let (url, _) = serve(Some(TOKEN)).await;
+        let list = format!("{url}/dead-letters");
+
+        let missing = client.get(&list).send().await.unwrap();
+        assert_eq!(missing.status(), StatusCode::UNAUTHORIZED);
+        let wrong = client.get(&list).bearer_auth("nope").send().await.unwrap();
+        assert_eq!(wrong.status(), StatusCode::UNAUTHORIZED);
+
+        let (url, _) = serve(None).await;
+        let disabled = client
+            .get(format!("{url}/dead-letters"))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(disabled.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_dead_letters_are_listed_without_payloads() {
+        let client = reqwest::Client::new();
+        let (url, store) = serve(Some(TOKEN)).await;
+        let id = dead_letter(&store, "main");
+
+        let list: Value = client
+            .get(format!("{url}/dead-letters"))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(list.as_array().map(Vec::len), Some(1));
+        assert_eq!(list[0]["id"], id);
+        assert_eq!(list[0]["destination"], "main");
+        assert!(list[0].get("payload").is_none());
+
+        let letter: Value = client
+            .get(format!("{url}/dead-letters/{id}"))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(letter["payload"]["action"], "created");
+    }
+
+    #[tokio::test]
+    async fn test_replay_queues_the_event_and_removes_the_letter() {
+        let client = reqwest::Client::new();
+        let (url, store) = serve(Some(TOKEN)).await;
+        let id = dead_letter(&store, "main");
+
+        let replayed = client
+            .post(format!("{url}/dead-letters/{id}/replay"))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(replayed.status(), StatusCode::ACCEPTED);
+        assert!(store.dead_letter(id).unwrap().is_none());
+        assert!(store.next_job("main").unwrap().is_some());
+        assert!(store.next_job("other").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_replay_keeps_letters_that_no_longer_route() {
+        let client = reqwest::Client::new();
+        let (url, store) = serve(Some(TOKEN)).await;
+        // `other` only accepts releases now.
+        let id = dead_letter(&store, "other");
+
+        let replayed = client
+            .post(format!("{url}/dead-letters/{id}/replay"))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(replayed.status(), StatusCode::CONFLICT);
+        assert!(store.dead_letter(id).unwrap().is_some());
+        assert!(store.next_job("other").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_replay_posts_milestones_that_failed_to_deliver() {
+        let client = reqwest::Client::new();
+        let (url, store) = serve(Some(TOKEN)).await;
+        // the milestone was queued, which saved the count, then dead-lettered.
+        store
+            .set_state("star_milestone:catppuccin-rfc/polybar", "1000")
+            .unwrap();
+        let id = dead_letter_of(&store, "star", "milestone", "main");
+
+        let replayed = client
+            .post(format!("{url}/dead-letters/{id}/replay"))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(replayed.status(), StatusCode::ACCEPTED);
+        let job = store
+            .next_job("main")
+            .unwrap()
+            .expect("milestone is queued");
+        assert_eq!(
+            job.message["embeds"][0]["title"],
+            "[catppuccin-rfc/polybar] Reached 1000 stars"
+        );
+    }
+}
@@ -6,3 +6,40 @@ pub const REPO_COLOR: catppuccin::Color = COLORS.yellow;
 pub const RELEASE_COLOR: catppuccin::Color = COLORS.mauve;
 pub const MEMBERSHIP_COLOR: catppuccin::Color = COLORS.base;
 pub const COMMIT_COLOR: catppuccin::Color = COLORS.teal;
+pub const FORCE_PUSH_COLOR: catppuccin::Color = COLORS.maroon;
+pub const TAG_CREATED_COLOR: catppuccin::Color = COLORS.lavender;
+pub const BRANCH_CREATED_COLOR: catppuccin::Color = COLORS.sky;
+pub const TAG_DELETED_COLOR: catppuccin::Color = COLORS.rosewater;
+pub const BRANCH_DELETED_COLOR: catppuccin::Color = COLORS.flamingo;
+pub const CI_FAILED_COLOR: catppuccin::Color = COLORS.red;
+pub const CI_FIXED_COLOR: catppuccin::Color = COLORS.green;
+pub const STAR_COLOR: catppuccin::Color = COLORS.yellow;
+pub const FORK_COLOR: catppuccin::Color = COLORS.sapphire;
+pub const SEVERITY_CRITICAL_COLOR: catppuccin::Color = COLORS.red;
+pub const SEVERITY_HIGH_COLOR: catppuccin::Color = COLORS.peach;
+pub const SEVERITY_MEDIUM_COLOR: catppuccin::Color = COLORS.yellow;
+pub const SEVERITY_LOW_COLOR: catppuccin::Color = COLORS.overlay2;
+pub const ALERT_RESOLVED_COLOR: catppuccin::Color = COLORS.green;
+pub const HOOK_CONNECTED_COLOR: catppuccin::Color = COLORS.green;
+pub const HOOK_DELETED_COLOR: catppuccin::Color = COLORS.red;
+pub const DEPLOYMENT_SUCCEEDED_COLOR: catppuccin::Color = COLORS.green;
+pub const DEPLOYMENT_FAILED_COLOR: catppuccin::Color = COLORS.red;
+pub const WIKI_COLOR: catppuccin::Color = COLORS.pink;
+pub const COMMENT_DELETED_COLOR: catppuccin::Color = COLORS.surface2;
+pub const PRERELEASE_COLOR: catppuccin::Color = COLORS.lavender;
+
+/// The colour for a severity from an advisory or a code scanning rule.
+pub fn severity_color(severity: &str) -> catppuccin::Color {
+    match severity {
+        "critical" => SEVERITY_CRITICAL_COLOR,
+        "high" | "error" => SEVERITY_HIGH_COLOR,
+        "medium" | "moderate" | "warning" => SEVERITY_MEDIUM_COLOR,
+        _ => SEVERITY_LOW_COLOR,
+    }
+}
+
+/// A colour packed as `0xrrggbb`, the way embeds want it.
+pub fn rgb(color: catppuccin::Color) -> u32 {
+    let rgb = color.rgb;
+    u32::from(rgb.r) << 16 | u32::from(rgb.g) << 8 | u32::from(rgb.b)
+}
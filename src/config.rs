@@ -0,0 +1,316 @@
+use std::{collections::HashMap, path::Path};
+
+use reqwest::{Method, Url};
+use serde::Deserialize;
+use thiserror::Error;
+use tracing::warn;
+
+use crate::{
+    dedupe::DedupeConfig,
+    delivery::{Auth, Body, Request, RetryPolicy},
+    events,
+    formats::{self, Format},
+    routing::{self, Filter, Restricted, RouteContext, Rule},
+    store::Source,
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+    pub destinations: HashMap<String, Destination>,
+    pub error_destination: String,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    #[serde(default)]
+    pub restricted: Restricted,
+    #[serde(default)]
+    pub delivery: RetryPolicy,
+    #[serde(default)]
+    pub dedupe: DedupeConfig,
+    #[serde(default)]
+    pub events: events::Settings,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Destination {
+    /// The webhook url, written directly in the file.
+    url: Option<String>,
+    /// The name of an environment variable holding the webhook url, so that
+    /// secrets don't need to be committed alongside the rules.
+    url_env: Option<String>,
+    /// Applied after routing, so a destination can be shared between rules
+    /// while only accepting some of the events they send it.
+    #[serde(default, rename = "match")]
+    filter: Filter,
+    /// The kind of webhook behind the url.
+    #[serde(default)]
+    format: Format,
+    /// The room id for a matrix destination, or the stream for a zulip one.
+    /// For both, the url is the server rather than a webhook.
+    room: Option<String>,
+    /// The access token for a matrix destination, the api key for a zulip
+    /// one, or the key that messages to a generic destination are signed with.
+    token: Option<String>,
+    /// The name of an environment variable holding the token.
+    token_env: Option<String>,
+    /// The email address of a zulip bot.
+    user: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to read config file: {0}")]
+    Read(#[from] std::io::Error),
+    #[error("failed to parse config file: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("unknown destination: {0}")]
+    UnknownDestination(String),
+    #[error("destination {0} must set exactly one of `url` or `url_env`")]
+    DestinationUrl(String),
+    #[error("destination {0} must not set both `token` and `token_env`")]
+    DestinationToken(String),
+    #[error("destination {destination} needs `{field}` for its format")]
+    MissingDestinationField {
+        destination: String,
+        field: &'static str,
+    },
+    #[error("destination {destination} can't use `{field}` with its format")]
+    UnexpectedDestinationField {
+        destination: String,
+        field: &'static str,
+    },
+    #[error("environment variable {env} for destination {destination} is not set")]
+    MissingEnv { destination: String, env: String },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl FileConfig {
+    /// Reads the config file at `path` and resolves every destination url.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let mut config: Self = std::fs::read_to_string(path)?.parse()?;
+        let optional = config.optional_destinations();
+        let mut disabled = vec![];
+        for (name, destination) in &mut config.destinations {
+            match destination.resolve(name) {
+                Err(Error::MissingEnv { env, .. }) if optional.contains(name) => {
+                    warn!(
+                        destination = name,
+                        env, "environment variable not set - disabling"
+                    );
+                    disabled.push(name.clone());
+                }
+                result => result?,
+            }
+        }
+        for name in disabled {
+            config.destinations.remove(&name);
+        }
+        Ok(config)
+    }
+
+    /// Destinations that restricted events go to. These are disabled rather
+    /// than failing to start when their environment isn't set, dropping
+    /// whatever is sent to them, so that deployments don't need a channel for
+    /// each. Errors always need somewhere to go.
+    fn optional_destinations(&self) -> Vec<String> {
+        self.restricted
+            .destinations()
+            .filter(|name| *name != self.error_destination)
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    pub fn destination(&self, name: &str) -> Option<&Destination> {
+        self.destinations.get(name)
+    }
+
+    /// Every destination that should receive the event, in rule order.
+    pub fn route(&self, ctx: &RouteContext) -> Vec<(&str, &Destination)> {
+        let names = self.restricted.destination(ctx).map_or_else(
+            || routing::route(&self.rules, ctx),
+            |restricted| self.route_restricted(restricted, ctx),
+        );
+        names
+            .into_iter()
+            .filter_map(|name| Some((name, self.destinations.get(name)?)))
+            .filter(|(_, destination)| destination.filter.matches(ctx))
+            .collect()
+    }
+
+    /// Only rules that name the event type can send a restricted event
+    /// elsewhere, so that a catch-all rule can't leak it. Rules that drop
+    /// events still apply, so that e.g. private repositories stay quiet.
+    fn route_restricted<'a>(&'a self, restricted: &'a str, ctx: &RouteContext) -> Vec<&'a str> {
+        let applicable: Vec<&Rule> = self
+            .rules
+            .iter()
+            .filter(|rule| {
+                rule.destinations.is_empty()
+                    || rule.filter.event.iter().any(|event| event == ctx.event)
+            })
+            .collect();
+        if applicable.iter().any(|rule| rule.filter.matches(ctx)) {
+            routing::route(applicable, ctx)
+        } else {
+            vec![restricted]
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let referenced = std::iter::once(&self.error_destination)
+            .chain(self.rules.iter().flat_map(|rule| &rule.destinations))
+            .chain(self.restricted.named());
+        for name in referenced {
+            if !self.destinations.contains_key(name) {
+                return Err(Error::UnknownDestination(name.clone()));
+            }
+        }
+        for (name, destination) in &self.destinations {
+            destination.validate(name)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for FileConfig {
+    type Err = Error;
+
+    /// Parses and validates a config without touching the environment.
+    fn from_str(s: &str) -> Result<Self> {
+        let config: Self = toml::from_str(s)?;
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+impl Destination {
+    fn validate(&self, name: &str) -> Result<()> {
+        if self.url.is_some() == self.url_env.is_some() {
+            return Err(Error::DestinationUrl(name.to_string()));
+        }
+        if self.token.is_some() && self.token_env.is_some() {
+            return Err(Error::DestinationToken(name.to_string()));
+        }
+        let fields = [
+            ("room", self.room.is_some(), self.format.needs_room()),
+            (
+                "token",
+                self.token.is_some() || self.token_env.is_some(),
+                self.format.needs_token(),
+            ),
+            ("user", self.user.is_some(), self.format.needs_user()),
+        ];
+        for (field, set, needed) in fields {
+            let destination = name.to_string();
+            if needed && !set {
+                return Err(Error::MissingDestinationField { destination, field });
+            }
+            if set && !needed {
+                return Err(Error::UnexpectedDestinationField { destination, field });
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve(&mut self, name: &str) -> Result<()> {
+        let read = |env: String| {
+            std::env::var(&env).map_err(|_| Error::MissingEnv {
+                destination: name.to_string(),
+                env,
+            })
+        };
+        if let Some(env) = self.url_env.take() {
+            self.url = Some(read(env)?);
+        }
+        if let Some(env) = self.token_env.take() {
+            self.token = Some(read(env)?);
+        }
+        Ok(())
+    }
+
+    /// The url to post to. Only empty before [`FileConfig::load`] has resolved
+    /// `url_env`.
+    pub fn url(&self) -> &str {
+        self.url.as_deref().unwrap_or_default()
+    }
+
+    pub const fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Renders a queued message for this destination. `txn` identifies the
+    /// message across retries, for destinations that can use it to ignore
+    /// repeats.
+    pub fn request(
+        &self,
+        message: &serde_json::Value,
+        source: Option<&Source>,
+        txn: &str,
+    ) -> Request {
+        let body = self.format.render(message, source);
+        let server = self.url().trim_end_matches('/');
+        let token = self.token.clone().unwrap_or_default();
+        match self.format {
+            Format::Matrix => Request {
+                method: Method::PUT,
+                url: self.matrix_url(txn),
+                body: Body::Json(body),
+                auth: Some(Auth::Bearer(token)),
+                headers: vec![],
+            },
+            Format::Zulip => Request {
+                method: Method::POST,
+                url: format!("{server}/api/v1/messages"),
+                body: Body::Form(vec![
+                    ("type".to_string(), "stream".to_string()),
+                    ("to".to_string(), self.room.clone().unwrap_or_default()),
+                    ("topic".to_string(), string(&body["topic"])),
+                    ("content".to_string(), string(&body["content"])),
+                ]),
+                auth: Some(Auth::Basic {
+                    user: self.user.clone().unwrap_or_default(),
+                    password: token,
+                }),
+                headers: vec![],
+            },
+            Format::Generic => Request {
+                headers: vec![(
+                    "X-Rockdove-Signature-256",
+                    formats::signature(&body, &token),
+                )],
+                ..Request::post(self.url().to_string(), body)
+            },
+            Format::Discord | Format::Slack | Format::Teams | Format::Mattermost => {
+                Request::post(self.url().to_string(), body)
+            }
+        }
+    }
+
+    /// Where a matrix message is sent, with the room and transaction ids
+    /// encoded since either can hold characters that mean something in a url.
+    fn matrix_url(&self, txn: &str) -> String {
+        // an invalid url is reported by the http client when it's sent.
+        let Ok(mut url) = Url::parse(self.url()) else {
+            return self.url().to_string();
+        };
+        if let Ok(mut path) = url.path_segments_mut() {
+            path.pop_if_empty().extend([
+                "_matrix",
+                "client",
+                "v3",
+                "rooms",
+                self.room.as_deref().unwrap_or_default(),
+                "send",
+                "m.room.message",
+                txn,
+            ]);
+        }
+        url.into()
+    }
+}
+
+fn string(value: &serde_json::Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
@@ -0,0 +1,207 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use serde::Deserialize;
+use tracing::error;
+
+use crate::store::{self, Store};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DedupeConfig {
+    pub enabled: bool,
+    /// How long a delivery id is remembered for.
+    pub ttl_secs: i64,
+    /// The most delivery ids remembered at once. The oldest are forgotten
+    /// first.
+    pub capacity: usize,
+    /// Keep delivery ids in the database so that they survive restarts.
+    pub persist: bool,
+    /// Honour the `X-Rockdove-Replay: true` header, which skips the check.
+    pub allow_replay_header: bool,
+}
+
+impl Default for DedupeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ttl_secs: 24 * 60 * 60,
+            capacity: 10_000,
+            persist: false,
+            allow_replay_header: false,
+        }
+    }
+}
+
+/// The `X-GitHub-Delivery` ids we've already handled, so that redeliveries
+/// don't post the same embed twice.
+pub struct Deliveries {
+    config: DedupeConfig,
+    store: Option<Store>,
+    seen: Mutex<Seen>,
+}
+
+#[derive(Default)]
+struct Seen {
+    at: HashMap<String, i64>,
+    order: VecDeque<(String, i64)>,
+}
+
+impl Deliveries {
+    pub fn new(config: DedupeConfig, store: &Store) -> store::Result<Self> {
+        let mut seen = Seen::default();
+        let store = if config.persist {
+            let cutoff = store::now() - config.ttl_secs;
+            for (id, at) in store.recent_deliveries(cutoff)? {
+                seen.insert(id, at, config.capacity);
+            }
+            Some(store.clone())
+        } else {
+            None
+        };
+        Ok(Self {
+            config,
+            store,
+            seen: Mutex::new(seen),
+        })
+    }
+
+    pub const fn allows_replay_header(&self) -> bool {
+        self.config.allow_replay_header
+    }
+
+    /// Records the delivery id, returning `false` if it had already been seen.
+    pub fn claim(&self, id: &str) -> bool {
+        self.claim_at(id, store::now())
+    }
+
+    fn claim_at(&self, id: &str, now: i64) -> bool {
+        if !self.config.enabled {
+            return true;
+        }
+
+        {
+            let mut seen = self.seen();
+            seen.expire(now - self.config.ttl_secs);
+            if seen.at.contains_key(id) {
+                return false;
+            }
+            seen.insert(id.to_string(), now, self.config.capacity);
+        }
+
+        if let Some(store) = &self.store
+            && let Err(e) = store.record_delivery(id, now, now - self.config.ttl_secs)
+        {
+            error!(%e, "failed to persist delivery id");
+        }
+        true
+    }
+
+    /// Forgets a claimed delivery id, so that github can retry a delivery we
+    /// failed to handle.
+    pub fn release(&self, id: &str) {
+        self.seen().at.remove(id);
+        if let Some(store) = &self.store
+            && let Err(e) = store.forget_delivery(id)
+        {
+            error!(%e, "failed to forget delivery id");
+        }
+    }
+
+    fn seen(&self) -> std::sync::MutexGuard<'_, Seen> {
+        self.seen
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl Seen {
+    fn insert(&mut self, id: String, at: i64, capacity: usize) {
+        self.at.insert(id.clone(), at);
+        self.order.push_back((id, at));
+        while self.at.len() > capacity {
+            self.pop_oldest();
+        }
+    }
+
+    fn expire(&mut self, cutoff: i64) {
+        while self.order.front().is_some_and(|(_, at)| *at <= cutoff) {
+            self.pop_oldest();
+        }
+    }
+
+    fn pop_oldest(&mut self) {
+        if let Some((id, at)) = self.order.pop_front()
+            // the id may have been released and claimed again since.
+            && self.at.get(&id) == Some(&at)
+        {
+            self.at.remove(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DedupeConfig, Deliveries};
+    use crate::store::Store;
+
+    fn deliveries(config: DedupeConfig) -> Deliveries {
+        Deliveries::new(config, &Store::open_in_memory().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_repeats_are_rejected() {
+        let deliveries = deliveries(DedupeConfig::default());
+        assert!(deliveries.claim_at("a", 0));
+        assert!(!deliveries.claim_at("a", 1));
+        assert!(deliveries.claim_at("b", 1));
+    }
+
+    #[test]
+    fn test_ids_expire() {
+        let deliveries = deliveries(DedupeConfig {
+            ttl_secs: 10,
+            ..DedupeConfig::default()
+        });
+        assert!(deliveries.claim_at("a", 0));
+        assert!(!deliveries.claim_at("a", 9));
+        assert!(deliveries.claim_at("a", 10));
+    }
+
+    #[test]
+    fn test_oldest_ids_are_evicted_over_capacity() {
+        let deliveries = deliveries(DedupeConfig {
+            capacity: 2,
+            ..DedupeConfig::default()
+        });
+        assert!(deliveries.claim_at("a", 0));
+        assert!(deliveries.claim_at("b", 0));
+        assert!(deliveries.claim_at("c", 0));
+        assert!(deliveries.claim_at("a", 0));
+        assert!(!deliveries.claim_at("c", 0));
+    }
+
+    #[test]
+    fn test_released_ids_can_be_claimed_again() {
+        let deliveries = deliveries(DedupeConfig::default());
+        assert!(deliveries.claim_at("a", 0));
+        deliveries.release("a");
+        assert!(deliveries.claim_at("a", 1));
+    }
+
+    #[test]
+    fn test_persisted_ids_survive_restarts() {
+        let store = Store::open_in_memory().unwrap();
+        let config = DedupeConfig {
+            persist: true,
+            ..DedupeConfig::default()
+        };
+        let deliveries = Deliveries::new(config.clone(), &store).unwrap();
+        assert!(deliveries.claim("a"));
+
+        let restarted = Deliveries::new(config, &store).unwrap();
+        assert!(!restarted.claim("a"));
+    }
+}
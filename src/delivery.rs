@@ -0,0 +1,346 @@
+use std::time::{Duration, Instant};
+
+use reqwest::{Method, Response, StatusCode, Url, header::HeaderMap};
+use serde::Deserialize;
+use thiserror::Error;
+use tracing::warn;
+
+/// How hard we try to get a message to a destination before giving up.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+    /// The total time budget, including time spent waiting on rate limits.
+    pub max_elapsed_secs: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay_ms: 500,
+            max_delay_ms: 30_000,
+            max_elapsed_secs: 300,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("rejected with {status}: {body}")]
+    Rejected { status: StatusCode, body: String },
+    #[error("gave up after {attempts} attempts: {last}")]
+    GaveUp { attempts: u32, last: String },
+    #[error("no message id in response")]
+    MissingId,
+    #[error("invalid url: {0}")]
+    InvalidUrl(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// What to do after a single attempt.
+enum Attempt {
+    /// Carries the response body, or null if it was empty.
+    Sent(serde_json::Value),
+    /// Wait exactly this long, because the destination told us to.
+    RateLimited(Duration),
+    /// Wait using backoff.
+    Failed(String),
+}
+
+/// A message ready to go to a destination, kept whole so that every retry
+/// sends exactly the same request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: Method,
+    pub url: String,
+    pub body: Body,
+    pub auth: Option<Auth>,
+    pub headers: Vec<(&'static str, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Body {
+    Json(serde_json::Value),
+    /// Sent url-encoded.
+    Form(Vec<(String, String)>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Auth {
+    Bearer(String),
+    Basic { user: String, password: String },
+}
+
+impl Request {
+    pub const fn post(url: String, body: serde_json::Value) -> Self {
+        Self {
+            method: Method::POST,
+            url,
+            body: Body::Json(body),
+            auth: None,
+            headers: vec![],
+        }
+    }
+}
+
+/// Sends `request`, retrying rate limits, server errors and connection errors
+/// until the policy's budget runs out. Any other client error is returned
+/// immediately since retrying won't fix it.
+pub async fn send(client: &reqwest::Client, request: &Request, policy: &RetryPolicy) -> Result<()> {
+    send_for_body(client, request, policy).await.map(drop)
+}
+
+/// Like [`send`], but waits for discord to create the message and returns its
+/// id so that it can be edited later.
+pub async fn post(
+    client: &reqwest::Client,
+    msg: &serde_json::Value,
+    url: &str,
+    policy: &RetryPolicy,
+) -> Result<String> {
+    let request = Request::post(wait_url(url)?.into(), msg.clone());
+    let created = send_for_body(client, &request, policy).await?;
+    created["id"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or(Error::MissingId)
+}
+
+/// Replaces the content of a message previously created with [`post`].
+pub async fn edit(
+    client: &reqwest::Client,
+    msg: &serde_json::Value,
+    url: &str,
+    id: &str,
+    policy: &RetryPolicy,
+) -> Result<()> {
+    let request = Request {
+        method: Method::PATCH,
+        ..Request::post(message_url(url, id)?.into(), msg.clone())
+    };
+    send(client, &request, policy).await
+}
+
+/// The webhook url asking discord to wait for the message to be created,
+/// keeping any query it already has, like `thread_id`.
+fn wait_url(url: &str) -> Result<Url> {
+    let mut url = Url::parse(url).map_err(|e| Error::InvalidUrl(e.to_string()))?;
+    url.query_pairs_mut().append_pair("wait", "true");
+    Ok(url)
+}
+
+/// The url of a message sent through the webhook, keeping its query.
+fn message_url(webhook: &str, id: &str) -> Result<Url> {
+    let mut url = Url::parse(webhook).map_err(|e| Error::InvalidUrl(e.to_string()))?;
+    url.path_segments_mut()
+        .map_err(|()| Error::InvalidUrl(webhook.to_string()))?
+        .pop_if_empty()
+        .extend(["messages", id]);
+    Ok(url)
+}
+
+async fn send_for_body(
+    client: &reqwest::Client,
+    request: &Request,
+    policy: &RetryPolicy,
+) -> Result<serde_json::Value> {
+    let started = Instant::now();
+    let budget = Duration::from_secs(policy.max_elapsed_secs);
+    let mut attempts = 0;
+
+    loop {
+        attempts += 1;
+        let (delay, last) = match attempt(client, request).await? {
+            Attempt::Sent(body) => return Ok(body),
+            Attempt::RateLimited(delay) => (delay, "rate limited".to_string()),
+            Attempt::Failed(reason) => (backoff(policy, attempts), reason),
+        };
+
+        if attempts >= policy.max_attempts || started.elapsed() + delay > budget {
+            return Err(Error::GaveUp { attempts, last });
+        }
+
+        warn!(attempts, ?delay, reason = last, "retrying hook");
+        tokio::time::sleep(delay).await;
+    }
+}
+
+async fn attempt(client: &reqwest::Client, request: &Request) -> Result<Attempt> {
+    let builder = client.request(request.method.clone(), &request.url);
+    let mut builder = match &request.body {
+        Body::Json(body) => builder.json(body),
+        Body::Form(fields) => builder.form(fields),
+    };
+    builder = match &request.auth {
+        Some(Auth::Bearer(token)) => builder.bearer_auth(token),
+        Some(Auth::Basic { user, password }) => builder.basic_auth(user, Some(password)),
+        None => builder,
+    };
+    for (name, value) in &request.headers {
+        builder = builder.header(*name, value);
+    }
+    let response = match builder.send().await {
+        Ok(response) => response,
+        Err(e) => return Ok(Attempt::Failed(e.to_string())),
+    };
+
+    let status = response.status();
+    if status.is_success() {
+        // discord tells us when we've used up the current bucket, so wait it
+        // out here rather than being told off on the next message.
+        if let Some(delay) = exhausted_bucket_reset(response.headers()) {
+            tokio::time::sleep(delay).await;
+        }
+        return Ok(Attempt::Sent(response.json().await.unwrap_or_default()));
+    }
+
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return Ok(Attempt::RateLimited(rate_limit_delay(response).await));
+    }
+
+    if status.is_server_error() {
+        return Ok(Attempt::Failed(format!("server error {status}")));
+    }
+
+    Err(Error::Rejected {
+        status,
+        body: response.text().await.unwrap_or_default(),
+    })
+}
+
+/// Discord puts `retry_after` (in seconds, possibly fractional) in the JSON
+/// body of a 429, and the same information in the `Retry-After` and
+/// `X-RateLimit-Reset-After` headers. Prefer the body since it's the most
+/// precise.
+async fn rate_limit_delay(response: Response) -> Duration {
+    let headers = response.headers().clone();
+    let body = response
+        .json::<serde_json::Value>()
+        .await
+        .unwrap_or_default();
+    retry_after(&headers, &body)
+}
+
+fn retry_after(headers: &HeaderMap, body: &serde_json::Value) -> Duration {
+    body["retry_after"]
+        .as_f64()
+        .or_else(|| header_secs(headers, "retry-after"))
+        .or_else(|| header_secs(headers, "x-ratelimit-reset-after"))
+        .map_or(Duration::from_secs(1), Duration::from_secs_f64)
+}
+
+fn exhausted_bucket_reset(headers: &HeaderMap) -> Option<Duration> {
+    if header_secs(headers, "x-ratelimit-remaining")? > 0.0 {
+        return None;
+    }
+    header_secs(headers, "x-ratelimit-reset-after").map(Duration::from_secs_f64)
+}
+
+fn header_secs(headers: &HeaderMap, name: &str) -> Option<f64> {
+    headers
+        .get(name)?
+        .to_str()
+        .ok()?
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+}
+
+/// Capped exponential backoff with jitter, so that a burst of failures across
+/// destinations doesn't retry in lockstep.
+fn backoff(policy: &RetryPolicy, attempts: u32) -> Duration {
+    let exponential = policy
+        .base_delay_ms
+        .saturating_mul(2_u64.saturating_pow(attempts.saturating_sub(1)));
+    let capped = exponential.min(policy.max_delay_ms);
+    Duration::from_millis(capped / 2 + rand::random_range(0..=capped / 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use serde_json::json;
+
+    use super::{RetryPolicy, backoff, exhausted_bucket_reset, message_url, retry_after, wait_url};
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn test_retry_after_prefers_body() {
+        let headers = headers(&[("retry-after", "5")]);
+        let body = json!({ "retry_after": 0.25 });
+        assert_eq!(retry_after(&headers, &body), Duration::from_millis(250));
+    }
+
+    #[test]
+    fn test_retry_after_falls_back_to_headers() {
+        let body = json!({});
+        assert_eq!(
+            retry_after(&headers(&[("retry-after", "3")]), &body),
+            Duration::from_secs(3)
+        );
+        assert_eq!(
+            retry_after(&headers(&[("x-ratelimit-reset-after", "1.5")]), &body),
+            Duration::from_millis(1500)
+        );
+    }
+
+    #[test]
+    fn test_exhausted_bucket_waits_for_reset() {
+        let exhausted = headers(&[
+            ("x-ratelimit-remaining", "0"),
+            ("x-ratelimit-reset-after", "2"),
+        ]);
+        assert_eq!(
+            exhausted_bucket_reset(&exhausted),
+            Some(Duration::from_secs(2))
+        );
+
+        let remaining = headers(&[
+            ("x-ratelimit-remaining", "4"),
+            ("x-ratelimit-reset-after", "2"),
+        ]);
+        assert_eq!(exhausted_bucket_reset(&remaining), None);
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let policy = RetryPolicy::default();
+        for attempts in 1..64 {
+            let delay = backoff(&policy, attempts);
+            assert!(delay <= Duration::from_millis(policy.max_delay_ms));
+            assert!(delay >= Duration::from_millis(policy.base_delay_ms / 2));
+        }
+    }
+
+    #[test]
+    fn test_message_urls_keep_the_thread() {
+        let url = "https://discord.com/api/webhooks/1/token?thread_id=2";
+        assert_eq!(
+            wait_url(url).unwrap().as_str(),
+            "https://discord.com/api/webhooks/1/token?thread_id=2&wait=true"
+        );
+        assert_eq!(
+            message_url(url, "3").unwrap().as_str(),
+            "https://discord.com/api/webhooks/1/token/messages/3?thread_id=2"
+        );
+        assert_eq!(
+            message_url("https://discord.com/api/webhooks/1/token/", "3")
+                .unwrap()
+                .as_str(),
+            "https://discord.com/api/webhooks/1/token/messages/3"
+        );
+    }
+}
@@ -1,9 +1,11 @@
 use octocrab::models::Author;
 use serde_json::json;
+
+use crate::{colors, store::Tracking};
 use thiserror::Error;
 
 const MAX_TITLE_LENGTH: usize = 256;
-const MAX_DESCRIPTION_LENGTH: usize = 640;
+pub const MAX_DESCRIPTION_LENGTH: usize = 640;
 const MAX_AUTHOR_NAME_LENGTH: usize = 256;
 
 #[derive(Default, Debug)]
@@ -13,6 +15,7 @@ pub struct EmbedBuilder {
     author: Option<Author>,
     description: Option<String>,
     color: Option<u32>,
+    tracking: Option<Tracking>,
 }
 
 #[derive(Debug, Error)]
@@ -49,11 +52,19 @@ impl EmbedBuilder {
     }
 
     pub fn color(&mut self, color: catppuccin::Color) -> &Self {
-        let rgb = color.rgb;
-        self.color = Some(u32::from(rgb.r) << 16 | u32::from(rgb.g) << 8 | u32::from(rgb.b));
+        self.color = Some(colors::rgb(color));
         self
     }
 
+    pub fn tracking(&mut self, tracking: Tracking) -> &Self {
+        self.tracking = Some(tracking);
+        self
+    }
+
+    pub const fn take_tracking(&mut self) -> Option<Tracking> {
+        self.tracking.take()
+    }
+
     pub fn try_build(self) -> Result<serde_json::Value> {
         Ok(json!({
             "embeds": [{
@@ -67,6 +78,36 @@ impl EmbedBuilder {
     }
 }
 
+/// Joins as many lines as fit in `max_length`, noting how many were left out
+/// rather than letting the description be cut off mid-line.
+pub fn fit_lines(lines: &[String], max_lines: usize, max_length: usize) -> String {
+    let mut shown_lines = vec![];
+    let mut length = 0;
+
+    for (shown, line) in lines.iter().enumerate() {
+        let left_after = lines.len() - shown - 1;
+        // leave room to say how many lines were left out if this is the last
+        // line that fits.
+        let reserved = if left_after == 0 {
+            0
+        } else {
+            more(left_after).len() + 1
+        };
+        if shown == max_lines || length + line.len() + reserved > max_length {
+            shown_lines.push(more(lines.len() - shown));
+            break;
+        }
+        length += line.len() + 1;
+        shown_lines.push(line.clone());
+    }
+
+    shown_lines.join("\n")
+}
+
+fn more(count: usize) -> String {
+    format!("...and {count} more")
+}
+
 fn embed_author(author: &Author) -> serde_json::Value {
     json!({
         "name": limit_text_length(&author.login, MAX_AUTHOR_NAME_LENGTH),
@@ -75,10 +116,25 @@ fn embed_author(author: &Author) -> serde_json::Value {
     })
 }
 
-fn limit_text_length(text: &str, max_length: usize) -> String {
+pub fn limit_text_length(text: &str, max_length: usize) -> String {
     if text.len() > max_length {
-        format!("{}...", &text[..max_length - 3])
+        format!("{}...", &text[..text.floor_char_boundary(max_length - 3)])
     } else {
         text.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::limit_text_length;
+
+    #[test]
+    fn test_long_text_is_cut_on_a_char_boundary() {
+        // each of these is three bytes, so the cut lands inside one.
+        let text = "ポートリクエスト".repeat(10);
+        let limited = limit_text_length(&text, 20);
+        assert_eq!(limited, "ポートリク...");
+        assert!(limited.len() <= 20);
+        assert_eq!(limit_text_length("short", 20), "short");
+    }
+}
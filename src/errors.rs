@@ -1,7 +1,12 @@
-use octocrab::models::webhook_events::WebhookEventType;
+use octocrab::models::{Author, webhook_events::WebhookEventType};
 use thiserror::Error;
 
-use crate::embed_builder;
+use crate::{
+    colors::COLORS,
+    delivery,
+    embed_builder::{self, EmbedBuilder},
+    store,
+};
 
 #[derive(Debug, Error)]
 pub enum RockdoveError {
@@ -19,6 +24,53 @@ pub enum RockdoveError {
 
     #[error(transparent)]
     EmbedBuilder(#[from] embed_builder::Error),
+
+    #[error(transparent)]
+    Store(#[from] store::Error),
+
+    #[error("failed to deliver to {destination}: {source}")]
+    Delivery {
+        destination: String,
+        source: delivery::Error,
+    },
 }
 
 pub type RockdoveResult<T> = Result<T, RockdoveError>;
+
+impl RockdoveError {
+    pub fn to_embed(&self) -> serde_json::Value {
+        let mut embed = EmbedBuilder::default();
+        embed.title("Error");
+        embed.description(&self.to_string());
+        embed.color(COLORS.red);
+        embed.author(make_hammy());
+        embed.url("https://goudham.com/");
+        embed
+            .try_build()
+            .expect("error embed should always be valid")
+    }
+}
+
+fn make_hammy() -> Author {
+    serde_json::from_value(serde_json::json!({
+      "login": "sgoudham",
+      "id": 58_985_301,
+      "node_id": "MDQ6VXNlcjU4OTg1MzAx",
+      "avatar_url": "https://avatars.githubusercontent.com/u/58985301?v=4",
+      "gravatar_id": "",
+      "url": "https://api.github.com/users/sgoudham",
+      "html_url": "https://github.com/sgoudham",
+      "followers_url": "https://api.github.com/users/sgoudham/followers",
+      "following_url": "https://api.github.com/users/sgoudham/following{/other_user}",
+      "gists_url": "https://api.github.com/users/sgoudham/gists{/gist_id}",
+      "starred_url": "https://api.github.com/users/sgoudham/starred{/owner}{/repo}",
+      "subscriptions_url": "https://api.github.com/users/sgoudham/subscriptions",
+      "organizations_url": "https://api.github.com/users/sgoudham/orgs",
+      "repos_url": "https://api.github.com/users/sgoudham/repos",
+      "events_url": "https://api.github.com/users/sgoudham/events{/privacy}",
+      "received_events_url": "https://api.github.com/users/sgoudham/received_events",
+      "type": "User",
+      "site_admin": false
+    }))
+    .expect("hammy is always valid :pepe_heart:")
+}
@@ -1,24 +1,191 @@
-use octocrab::models::webhook_events::{WebhookEvent, WebhookEventPayload};
+use std::cell::RefCell;
+
+use octocrab::models::webhook_events::{WebhookEvent, WebhookEventPayload, WebhookEventType};
+use serde::Deserialize;
 use tracing::info;
 
 use crate::{
     embed_builder::EmbedBuilder,
     errors::{RockdoveError, RockdoveResult},
+    store::{self, Store, Tracking},
 };
 
+mod check_suite;
+mod code_scanning_alert;
 mod commit_comment;
+mod create;
+mod delete;
+mod dependabot_alert;
+mod deployment_status;
 mod discussion;
 mod discussion_comment;
+mod fork;
+mod gollum;
 mod issue_comment;
 mod issues;
 mod membership;
+mod meta;
+mod organization;
+mod ping;
+mod public;
 mod pull_request;
 mod pull_request_review;
 mod pull_request_review_comment;
+mod push;
 mod release;
 mod repository;
+mod repository_advisory;
+mod secret_scanning_alert;
+mod star;
+mod team;
+mod workflow_run;
+
+/// Everything a renderer might need besides the event itself.
+pub struct Context<'a> {
+    pub settings: &'a Settings,
+    /// For events that depend on what happened before them.
+    pub store: &'a Store,
+    /// The raw payload, for the fields that octocrab doesn't keep.
+    pub payload: &'a serde_json::Value,
+    /// Whether the event is being replayed after its delivery failed, so the
+    /// store already remembers it.
+    pub replay: bool,
+}
+
+/// What renderers remember between events. Writes are held back until the
+/// message is queued, so a message that never goes out isn't remembered as
+/// sent.
+pub struct State<'a> {
+    store: &'a Store,
+    replay: bool,
+    pending: RefCell<Vec<(String, String)>>,
+}
+
+impl<'a> State<'a> {
+    pub const fn new(store: &'a Store, replay: bool) -> Self {
+        Self {
+            store,
+            replay,
+            pending: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> store::Result<Option<String>> {
+        let pending = self
+            .pending
+            .borrow()
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.clone());
+        pending.map_or_else(|| self.store.state(key), |value| Ok(Some(value)))
+    }
+
+    pub fn set(&self, key: &str, value: &str) {
+        self.pending
+            .borrow_mut()
+            .push((key.to_string(), value.to_string()));
+    }
+
+    /// Whether the event already went through once, so anything that would
+    /// hold it back as a repeat should let it through.
+    pub const fn replay(&self) -> bool {
+        self.replay
+    }
+
+    fn into_pending(self) -> Vec<(String, String)> {
+        self.pending.into_inner()
+    }
+}
+
+/// Rendering options for individual events, from the `[events]` table of the
+/// rules file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub issues: issues::Settings,
+    pub pull_request: pull_request::Settings,
+    pub discussion: discussion::Settings,
+    pub push: push::Settings,
+    pub create: create::Settings,
+    pub delete: delete::Settings,
+    pub workflow_run: workflow_run::Settings,
+    pub check_suite: check_suite::Settings,
+    pub star: star::Settings,
+    pub fork: fork::Settings,
+    pub deployment_status: deployment_status::Settings,
+    pub issue_comment: issue_comment::Settings,
+    pub pull_request_review_comment: pull_request_review_comment::Settings,
+    pub release: release::Settings,
+}
+
+/// The `X-GitHub-Event` names that can be turned into an embed, though
+/// depending on the action and settings not every event of these types is.
+pub const RENDERED_EVENTS: &[&str] = &[
+    "check_suite",
+    "code_scanning_alert",
+    "commit_comment",
+    "create",
+    "delete",
+    "dependabot_alert",
+    "deployment_status",
+    "discussion",
+    "discussion_comment",
+    "fork",
+    "gollum",
+    "issue_comment",
+    "issues",
+    "membership",
+    "meta",
+    "organization",
+    "ping",
+    "public",
+    "pull_request",
+    "pull_request_review",
+    "pull_request_review_comment",
+    "push",
+    "release",
+    "repository",
+    "repository_advisory",
+    "secret_scanning_alert",
+    "star",
+    "team",
+    "watch",
+    "workflow_run",
+];
+
+/// Events whose octocrab payload expects fields that github doesn't send. They
+/// are parsed with an untyped payload and rendered from the raw json instead.
+const UNTYPED_EVENTS: &[&str] = &["code_scanning_alert", "repository_advisory"];
+
+pub fn parse(event_type: &str, body: &str) -> serde_json::Result<WebhookEvent> {
+    if !UNTYPED_EVENTS.contains(&event_type) {
+        return WebhookEvent::try_from_header_and_body(event_type, body);
+    }
+    // octocrab leaves the payload of any event it doesn't know untyped, so
+    // parse it as one of those and put the real kind back afterwards.
+    let mut event = WebhookEvent::try_from_header_and_body("untyped", body)?;
+    event.kind = serde_json::from_value(serde_json::Value::from(event_type))?;
+    Ok(event)
+}
 
-pub fn make_embed(event: WebhookEvent) -> RockdoveResult<Option<serde_json::Value>> {
+/// Removes whatever mustn't be stored from an incoming payload.
+pub fn redact(event_type: &str, payload: &mut serde_json::Value) {
+    if event_type == "secret_scanning_alert" {
+        secret_scanning_alert::redact(payload);
+    }
+}
+
+/// A rendered event, ready to be queued for each of its destinations.
+#[derive(Debug)]
+pub struct Message {
+    pub body: serde_json::Value,
+    pub tracking: Option<Tracking>,
+    /// State to save along with the queued jobs.
+    pub state: Vec<(String, String)>,
+}
+
+pub fn make_embed(event: WebhookEvent, ctx: &Context) -> RockdoveResult<Option<Message>> {
     let sender = event
         .sender
         .clone()
@@ -27,26 +194,45 @@ pub fn make_embed(event: WebhookEvent) -> RockdoveResult<Option<serde_json::Valu
             field: "sender",
         })?;
 
-    let Some(mut embed) = begin_embed(event)? else {
+    let state = State::new(ctx.store, ctx.replay);
+    let Some(mut embed) = begin_embed(event, ctx, &state)? else {
         info!("ignoring event");
+        // nothing goes out, so there's nothing to wait for.
+        ctx.store.set_states(&state.into_pending())?;
         return Ok(None);
     };
 
     embed.author(sender);
-    Ok(Some(embed.try_build()?))
+    let tracking = embed.take_tracking();
+    Ok(Some(Message {
+        body: embed.try_build()?,
+        tracking,
+        state: state.into_pending(),
+    }))
 }
 
-fn begin_embed(event: WebhookEvent) -> RockdoveResult<Option<EmbedBuilder>> {
+fn begin_embed(
+    event: WebhookEvent,
+    ctx: &Context,
+    state: &State,
+) -> RockdoveResult<Option<EmbedBuilder>> {
+    let settings = ctx.settings;
     match event.specific.clone() {
         WebhookEventPayload::Repository(specifics) => repository::make_embed(event, &specifics),
-        WebhookEventPayload::Discussion(specifics) => discussion::make_embed(event, &specifics),
+        WebhookEventPayload::Discussion(specifics) => {
+            discussion::make_embed(event, &specifics, &settings.discussion)
+        }
         WebhookEventPayload::DiscussionComment(specifics) => {
             discussion_comment::make_embed(event, &specifics)
         }
-        WebhookEventPayload::Issues(specifics) => issues::make_embed(event, &specifics),
-        WebhookEventPayload::PullRequest(specifics) => pull_request::make_embed(event, &specifics),
+        WebhookEventPayload::Issues(specifics) => {
+            issues::make_embed(event, &specifics, &settings.issues, ctx.payload)
+        }
+        WebhookEventPayload::PullRequest(specifics) => {
+            pull_request::make_embed(event, &specifics, &settings.pull_request, state)
+        }
         WebhookEventPayload::IssueComment(specifics) => {
-            issue_comment::make_embed(event, &specifics)
+            issue_comment::make_embed(event, &specifics, &settings.issue_comment)
         }
         WebhookEventPayload::CommitComment(specifics) => {
             commit_comment::make_embed(event, &specifics)
@@ -55,10 +241,86 @@ fn begin_embed(event: WebhookEvent) -> RockdoveResult<Option<EmbedBuilder>> {
             pull_request_review::make_embed(event, &specifics)
         }
         WebhookEventPayload::PullRequestReviewComment(specifics) => {
-            pull_request_review_comment::make_embed(event, &specifics)
+            pull_request_review_comment::make_embed(
+                event,
+                &specifics,
+                &settings.pull_request_review_comment,
+            )
+        }
+        WebhookEventPayload::Release(specifics) => {
+            release::make_embed(event, &specifics, &settings.release)
         }
-        WebhookEventPayload::Release(specifics) => release::make_embed(event, &specifics),
         WebhookEventPayload::Membership(specifics) => membership::make_embed(event, &specifics),
+        WebhookEventPayload::Push(specifics) => push::make_embed(event, &specifics, &settings.push),
+        WebhookEventPayload::Create(specifics) => {
+            create::make_embed(event, &specifics, &settings.create)
+        }
+        WebhookEventPayload::Delete(specifics) => {
+            delete::make_embed(event, &specifics, &settings.delete)
+        }
+        WebhookEventPayload::WorkflowRun(specifics) => {
+            workflow_run::make_embed(event, &specifics, &settings.workflow_run, state)
+        }
+        WebhookEventPayload::CheckSuite(specifics) => {
+            check_suite::make_embed(event, &specifics, &settings.check_suite, state)
+        }
+        WebhookEventPayload::Star(specifics) => star::make_embed(
+            event,
+            &star::Source::Star(&specifics),
+            &settings.star,
+            state,
+        ),
+        WebhookEventPayload::Watch(_) => {
+            star::make_embed(event, &star::Source::Watch, &settings.star, state)
+        }
+        WebhookEventPayload::Fork(specifics) => {
+            fork::make_embed(event, &specifics, &settings.fork, state)
+        }
+        WebhookEventPayload::Gollum(specifics) => gollum::make_embed(event, &specifics),
+        WebhookEventPayload::DependabotAlert(specifics) => {
+            dependabot_alert::make_embed(event, &specifics)
+        }
+        WebhookEventPayload::SecretScanningAlert(specifics) => {
+            secret_scanning_alert::make_embed(event, &specifics)
+        }
+        WebhookEventPayload::Unknown(specifics) => match event.kind {
+            WebhookEventType::CodeScanningAlert => {
+                code_scanning_alert::make_embed(event, &specifics)
+            }
+            WebhookEventType::RepositoryAdvisory => {
+                repository_advisory::make_embed(event, &specifics)
+            }
+            _ => Ok(None),
+        },
+        WebhookEventPayload::Organization(specifics) => {
+            organization::make_embed(event, &specifics, ctx.payload)
+        }
+        WebhookEventPayload::Team(specifics) => team::make_embed(event, &specifics),
+        WebhookEventPayload::Public(_) => public::make_embed(event),
+        WebhookEventPayload::DeploymentStatus(specifics) => {
+            deployment_status::make_embed(event, &specifics, &settings.deployment_status, state)
+        }
+        WebhookEventPayload::Ping(specifics) => ping::make_embed(event, &specifics),
+        WebhookEventPayload::Meta(specifics) => meta::make_embed(event, &specifics),
         _ => Ok(None),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::RENDERED_EVENTS;
+
+    #[test]
+    fn test_every_event_with_fixtures_is_listed_as_rendered() {
+        let fixtures = std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"))
+            .expect("fixtures exist");
+        for entry in fixtures {
+            let name = entry.unwrap().file_name();
+            let name = name.to_str().unwrap();
+            assert!(
+                RENDERED_EVENTS.contains(&name),
+                "{name} is missing from RENDERED_EVENTS"
+            );
+        }
+    }
+}
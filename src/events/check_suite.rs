@@ -0,0 +1,182 @@
+use octocrab::models::webhook_events::{
+    WebhookEvent,
+    payload::{CheckSuiteWebhookEventAction, CheckSuiteWebhookEventPayload},
+};
+use serde::Deserialize;
+
+use crate::{
+    colors::{CI_FAILED_COLOR, CI_FIXED_COLOR},
+    embed_builder::EmbedBuilder,
+    errors::{RockdoveError, RockdoveResult},
+    events::{
+        State,
+        workflow_run::{self, HeadCommit, Outcome},
+    },
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// The conclusions that count as a failure worth posting.
+    pub conclusions: Vec<String>,
+    /// Post when a check suite succeeds after a failure.
+    pub notify_fixed: bool,
+    /// Apps whose check suites are ignored. GitHub Actions is ignored by
+    /// default since its runs are already posted as `workflow_run` events.
+    pub ignored_apps: Vec<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            conclusions: workflow_run::default_conclusions(),
+            notify_fixed: true,
+            ignored_apps: vec!["github-actions".to_string()],
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CheckSuite {
+    head_branch: Option<String>,
+    head_sha: String,
+    conclusion: Option<String>,
+    app: App,
+    head_commit: Option<HeadCommit>,
+}
+
+#[derive(Deserialize)]
+struct App {
+    id: u64,
+    slug: String,
+    name: String,
+}
+
+pub fn make_embed(
+    event: WebhookEvent,
+    specifics: &CheckSuiteWebhookEventPayload,
+    settings: &Settings,
+    state: &State,
+) -> RockdoveResult<Option<EmbedBuilder>> {
+    if !matches!(specifics.action, CheckSuiteWebhookEventAction::Completed) {
+        return Ok(None);
+    }
+
+    let suite: CheckSuite =
+        serde_json::from_value(specifics.check_suite.clone()).map_err(|_| {
+            RockdoveError::InvalidField {
+                event_type: event.kind.clone(),
+                field: "check_suite",
+            }
+        })?;
+
+    if settings.ignored_apps.contains(&suite.app.slug) {
+        return Ok(None);
+    }
+
+    let repo = event
+        .repository
+        .ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "repository",
+        })?;
+
+    // a suite doesn't say where its commit came from. github usually leaves
+    // the branch empty for one from a fork, which skips it along with every
+    // other branch, but a fork's default branch can otherwise pass for ours.
+    let Some(branch) = suite
+        .head_branch
+        .as_deref()
+        .filter(|branch| repo.default_branch.as_deref() == Some(*branch))
+    else {
+        return Ok(None);
+    };
+    let Some(conclusion) = suite.conclusion.as_deref() else {
+        return Ok(None);
+    };
+
+    let repo_name = repo.full_name.unwrap_or(repo.name);
+    let Some(outcome) = workflow_run::track(
+        state,
+        &format!("check_suite:{repo_name}:{}", suite.app.id),
+        conclusion,
+        &settings.conclusions,
+        settings.notify_fixed,
+    )?
+    else {
+        return Ok(None);
+    };
+
+    let mut embed = EmbedBuilder::default();
+
+    embed.title(&format!(
+        "[{}] Checks from {} {} on {}",
+        repo_name,
+        suite.app.name,
+        match outcome {
+            Outcome::Failed(ref verb) => verb,
+            Outcome::Fixed => "fixed",
+        },
+        branch,
+    ));
+
+    let html_url = repo.html_url.ok_or_else(|| RockdoveError::MissingField {
+        event_type: event.kind.clone(),
+        field: "repository.html_url",
+    })?;
+
+    // check suites don't have a page of their own, so link to the commit's checks.
+    embed.url(&format!("{html_url}/commit/{}/checks", suite.head_sha));
+
+    embed.description(&workflow_run::describe(
+        html_url.as_str(),
+        &suite.head_sha,
+        suite.head_commit.as_ref(),
+        conclusion,
+    ));
+
+    embed.color(match outcome {
+        Outcome::Failed(_) => CI_FAILED_COLOR,
+        Outcome::Fixed => CI_FIXED_COLOR,
+    });
+
+    Ok(Some(embed))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{events::Settings, snapshot_test, store::Store, tests::render};
+
+    #[test]
+    fn failure() {
+        snapshot_test!("check_suite", "failure");
+    }
+
+    #[test]
+    fn test_suites_from_forks_are_ignored() {
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        let settings = Settings::default();
+        assert_eq!(
+            render("check_suite", "failure_from_fork", &settings, &store),
+            None
+        );
+        assert_eq!(
+            store
+                .state("check_suite:catppuccin-rfc/polybar:15368")
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_github_actions_is_ignored() {
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        let embed = render(
+            "check_suite",
+            "github_actions",
+            &Settings::default(),
+            &store,
+        );
+        assert_eq!(embed, None, "github actions is covered by workflow_run");
+    }
+}
@@ -0,0 +1,127 @@
+use octocrab::models::webhook_events::WebhookEvent;
+use serde::Deserialize;
+
+use crate::{
+    colors::{ALERT_RESOLVED_COLOR, severity_color},
+    embed_builder::EmbedBuilder,
+    errors::{RockdoveError, RockdoveResult},
+};
+
+/// The parts of the payload that we use. octocrab's own payload expects
+/// fields that github doesn't send, so the event arrives untyped.
+#[derive(Deserialize)]
+struct Payload {
+    action: String,
+    alert: Alert,
+}
+
+#[derive(Deserialize)]
+struct Alert {
+    number: u64,
+    html_url: String,
+    rule: Rule,
+    tool: Tool,
+    most_recent_instance: Option<Instance>,
+}
+
+#[derive(Deserialize)]
+struct Rule {
+    id: Option<String>,
+    description: String,
+    severity: Option<String>,
+    security_severity_level: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Tool {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Instance {
+    location: Option<Location>,
+}
+
+#[derive(Deserialize)]
+struct Location {
+    path: String,
+    start_line: Option<u64>,
+}
+
+pub fn make_embed(
+    event: WebhookEvent,
+    specifics: &serde_json::Value,
+) -> RockdoveResult<Option<EmbedBuilder>> {
+    let payload: Payload =
+        serde_json::from_value(specifics.clone()).map_err(|_| RockdoveError::InvalidField {
+            event_type: event.kind.clone(),
+            field: "alert",
+        })?;
+
+    let (verb, resolved) = match payload.action.as_str() {
+        "created" => ("opened", false),
+        "reopened" | "reopened_by_user" => ("reopened", false),
+        "fixed" => ("fixed", true),
+        "closed_by_user" => ("closed", true),
+        _ => return Ok(None),
+    };
+    let alert = payload.alert;
+
+    let repo = event
+        .repository
+        .ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "repository",
+        })?;
+
+    let mut embed = EmbedBuilder::default();
+
+    embed.title(&format!(
+        "[{}] Code scanning alert #{} {}: {}",
+        repo.full_name.unwrap_or(repo.name),
+        alert.number,
+        verb,
+        alert.rule.description,
+    ));
+
+    embed.url(&alert.html_url);
+
+    // security rules have a severity of their own, other rules only have the
+    // tool's error/warning/note level.
+    let severity = alert
+        .rule
+        .security_severity_level
+        .as_deref()
+        .or(alert.rule.severity.as_deref())
+        .unwrap_or("unknown");
+    let mut lines = vec![format!("**Severity:** {severity}")];
+    if let Some(id) = &alert.rule.id {
+        lines.push(format!("**Rule:** `{id}`"));
+    }
+    lines.push(format!("**Tool:** {}", alert.tool.name));
+    if let Some(location) = alert.most_recent_instance.and_then(|i| i.location) {
+        lines.push(match location.start_line {
+            Some(line) => format!("**Location:** `{}:{}`", location.path, line),
+            None => format!("**Location:** `{}`", location.path),
+        });
+    }
+    embed.description(&lines.join("\n"));
+
+    embed.color(if resolved {
+        ALERT_RESOLVED_COLOR
+    } else {
+        severity_color(severity)
+    });
+
+    Ok(Some(embed))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snapshot_test;
+
+    #[test]
+    fn created() {
+        snapshot_test!("code_scanning_alert", "created");
+    }
+}
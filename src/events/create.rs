@@ -0,0 +1,105 @@
+use octocrab::models::webhook_events::{
+    WebhookEvent,
+    payload::{CreateWebhookEventPayload, RefType},
+};
+use serde::Deserialize;
+
+use crate::{
+    colors::{BRANCH_CREATED_COLOR, TAG_CREATED_COLOR},
+    embed_builder::EmbedBuilder,
+    errors::{RockdoveError, RockdoveResult},
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// The kinds of ref (`tag` or `branch`) to post about.
+    pub ref_types: Vec<RefType>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            ref_types: vec![RefType::Tag],
+        }
+    }
+}
+
+pub fn make_embed(
+    event: WebhookEvent,
+    specifics: &CreateWebhookEventPayload,
+    settings: &Settings,
+) -> RockdoveResult<Option<EmbedBuilder>> {
+    if !settings.ref_types.contains(&specifics.ref_type) {
+        return Ok(None);
+    }
+
+    let repo = event
+        .repository
+        .ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "repository",
+        })?;
+
+    let mut embed = EmbedBuilder::default();
+
+    let repo_name = repo.full_name.unwrap_or(repo.name);
+
+    embed.title(&format!(
+        "[{}] New {} created: {}",
+        repo_name,
+        match specifics.ref_type {
+            RefType::Tag => "tag",
+            RefType::Branch => "branch",
+            _ => return Ok(None),
+        },
+        specifics.r#ref,
+    ));
+
+    embed.url(&format!(
+        "{}/tree/{}",
+        repo.html_url.ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "repository.html_url",
+        })?,
+        specifics.r#ref,
+    ));
+
+    embed.color(match specifics.ref_type {
+        RefType::Branch => BRANCH_CREATED_COLOR,
+        _ => TAG_CREATED_COLOR,
+    });
+
+    Ok(Some(embed))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{events::Settings, snapshot_test};
+
+    use octocrab::models::webhook_events::payload::RefType;
+
+    #[test]
+    fn tag() {
+        snapshot_test!("create", "tag");
+    }
+
+    #[test]
+    fn branch() {
+        let mut settings = Settings::default();
+        settings.create.ref_types = vec![RefType::Tag, RefType::Branch];
+        snapshot_test!("create", "branch", &settings);
+    }
+
+    #[test]
+    fn test_branches_are_ignored_by_default() {
+        let store = crate::store::Store::open_in_memory().expect("in-memory store opens");
+        let embed = crate::tests::render(
+            "create",
+            "branch",
+            &crate::events::Settings::default(),
+            &store,
+        );
+        assert_eq!(embed, None, "new branches should be ignored by default");
+    }
+}
@@ -0,0 +1,94 @@
+use octocrab::models::webhook_events::{
+    WebhookEvent,
+    payload::{DeleteWebhookEventPayload, RefType},
+};
+use serde::Deserialize;
+
+use crate::{
+    colors::{BRANCH_DELETED_COLOR, TAG_DELETED_COLOR},
+    embed_builder::EmbedBuilder,
+    errors::{RockdoveError, RockdoveResult},
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// The kinds of ref (`tag` or `branch`) to post about.
+    pub ref_types: Vec<RefType>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            ref_types: vec![RefType::Branch],
+        }
+    }
+}
+
+pub fn make_embed(
+    event: WebhookEvent,
+    specifics: &DeleteWebhookEventPayload,
+    settings: &Settings,
+) -> RockdoveResult<Option<EmbedBuilder>> {
+    if !settings.ref_types.contains(&specifics.ref_type) {
+        return Ok(None);
+    }
+
+    let repo = event
+        .repository
+        .ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "repository",
+        })?;
+
+    let mut embed = EmbedBuilder::default();
+
+    let repo_name = repo.full_name.unwrap_or(repo.name);
+
+    // the ref is gone, so link to the list of what's left instead.
+    let (kind, list) = match specifics.ref_type {
+        RefType::Tag => ("Tag", "tags"),
+        RefType::Branch => ("Branch", "branches"),
+        _ => return Ok(None),
+    };
+
+    embed.title(&format!(
+        "[{}] {} deleted: {}",
+        repo_name, kind, specifics.r#ref,
+    ));
+
+    embed.url(&format!(
+        "{}/{}",
+        repo.html_url.ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "repository.html_url",
+        })?,
+        list,
+    ));
+
+    embed.color(match specifics.ref_type {
+        RefType::Branch => BRANCH_DELETED_COLOR,
+        _ => TAG_DELETED_COLOR,
+    });
+
+    Ok(Some(embed))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{events::Settings, snapshot_test};
+
+    use octocrab::models::webhook_events::payload::RefType;
+
+    #[test]
+    fn branch() {
+        snapshot_test!("delete", "branch");
+    }
+
+    #[test]
+    fn tag() {
+        let mut settings = Settings::default();
+        settings.delete.ref_types = vec![RefType::Tag];
+        snapshot_test!("delete", "tag", &settings);
+    }
+}
@@ -0,0 +1,134 @@
+use octocrab::models::webhook_events::{
+    WebhookEvent,
+    payload::{DependabotAlertWebhookEventAction, DependabotAlertWebhookEventPayload},
+};
+use serde::Deserialize;
+
+use crate::{
+    colors::{ALERT_RESOLVED_COLOR, severity_color},
+    embed_builder::EmbedBuilder,
+    errors::{RockdoveError, RockdoveResult},
+};
+
+#[derive(Deserialize)]
+struct Alert {
+    number: u64,
+    html_url: String,
+    dependency: Dependency,
+    security_advisory: Advisory,
+    security_vulnerability: Vulnerability,
+}
+
+#[derive(Deserialize)]
+struct Dependency {
+    package: Package,
+    manifest_path: String,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    ecosystem: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Advisory {
+    ghsa_id: String,
+    summary: String,
+}
+
+#[derive(Deserialize)]
+struct Vulnerability {
+    severity: String,
+    vulnerable_version_range: String,
+    first_patched_version: Option<PatchedVersion>,
+}
+
+#[derive(Deserialize)]
+struct PatchedVersion {
+    identifier: String,
+}
+
+pub fn make_embed(
+    event: WebhookEvent,
+    specifics: &DependabotAlertWebhookEventPayload,
+) -> RockdoveResult<Option<EmbedBuilder>> {
+    let (verb, resolved) = match specifics.action {
+        DependabotAlertWebhookEventAction::Created => ("opened", false),
+        DependabotAlertWebhookEventAction::Reopened
+        | DependabotAlertWebhookEventAction::AutoReopened => ("reopened", false),
+        DependabotAlertWebhookEventAction::Reintroduced => ("reintroduced", false),
+        DependabotAlertWebhookEventAction::Fixed => ("fixed", true),
+        DependabotAlertWebhookEventAction::Dismissed
+        | DependabotAlertWebhookEventAction::AutoDismissed => ("dismissed", true),
+        _ => return Ok(None),
+    };
+
+    let alert: Alert = serde_json::from_value(specifics.alert.clone()).map_err(|_| {
+        RockdoveError::InvalidField {
+            event_type: event.kind.clone(),
+            field: "alert",
+        }
+    })?;
+
+    let repo = event
+        .repository
+        .ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "repository",
+        })?;
+
+    let mut embed = EmbedBuilder::default();
+
+    embed.title(&format!(
+        "[{}] Dependabot alert #{} {}: {}",
+        repo.full_name.unwrap_or(repo.name),
+        alert.number,
+        verb,
+        alert.security_advisory.summary,
+    ));
+
+    embed.url(&alert.html_url);
+
+    let vulnerability = &alert.security_vulnerability;
+    let mut lines = vec![
+        format!("**Severity:** {}", vulnerability.severity),
+        format!(
+            "**Package:** {} ({})",
+            alert.dependency.package.name, alert.dependency.package.ecosystem
+        ),
+        format!("**Manifest:** `{}`", alert.dependency.manifest_path),
+        format!(
+            "**Vulnerable versions:** `{}`",
+            vulnerability.vulnerable_version_range
+        ),
+    ];
+    if let Some(patched) = &vulnerability.first_patched_version {
+        lines.push(format!("**Patched in:** `{}`", patched.identifier));
+    }
+    lines.push(format!("**Advisory:** {}", alert.security_advisory.ghsa_id));
+    embed.description(&lines.join("\n"));
+
+    embed.color(if resolved {
+        ALERT_RESOLVED_COLOR
+    } else {
+        severity_color(&vulnerability.severity)
+    });
+
+    Ok(Some(embed))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snapshot_test;
+
+    use yare::parameterized;
+
+    #[parameterized(
+        created = { "created" },
+        fixed = { "fixed" },
+    )]
+    fn snapshot(event_type: &str) {
+        snapshot_test!("dependabot_alert", event_type);
+    }
+}
@@ -0,0 +1,205 @@
+use octocrab::models::webhook_events::{
+    WebhookEvent,
+    payload::{DeploymentStatusWebhookEventAction, DeploymentStatusWebhookEventPayload},
+};
+use serde::Deserialize;
+
+use crate::{
+    colors::{DEPLOYMENT_FAILED_COLOR, DEPLOYMENT_SUCCEEDED_COLOR},
+    embed_builder::EmbedBuilder,
+    errors::{RockdoveError, RockdoveResult},
+    events::State,
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// The environments that are posted, ignoring case. An empty list posts
+    /// every environment.
+    pub environments: Vec<String>,
+    /// The deployment states that are posted.
+    pub states: Vec<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            environments: vec!["production".to_string()],
+            states: ["success", "failure", "error"]
+                .map(ToString::to_string)
+                .to_vec(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Deployment {
+    sha: String,
+    r#ref: String,
+    creator: Option<User>,
+}
+
+#[derive(Deserialize)]
+struct DeploymentStatus {
+    state: String,
+    environment: String,
+    environment_url: Option<String>,
+    target_url: Option<String>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct User {
+    login: String,
+}
+
+pub fn make_embed(
+    event: WebhookEvent,
+    specifics: &DeploymentStatusWebhookEventPayload,
+    settings: &Settings,
+    state: &State,
+) -> RockdoveResult<Option<EmbedBuilder>> {
+    if !matches!(
+        specifics.action,
+        DeploymentStatusWebhookEventAction::Created
+    ) {
+        return Ok(None);
+    }
+
+    let status = DeploymentStatus::deserialize(&specifics.deployment_status).map_err(|_| {
+        RockdoveError::InvalidField {
+            event_type: event.kind.clone(),
+            field: "deployment_status",
+        }
+    })?;
+    let deployment = Deployment::deserialize(&specifics.deployment).map_err(|_| {
+        RockdoveError::InvalidField {
+            event_type: event.kind.clone(),
+            field: "deployment",
+        }
+    })?;
+
+    if !settings.states.contains(&status.state)
+        || !(settings.environments.is_empty()
+            || settings
+                .environments
+                .iter()
+                .any(|environment| environment.eq_ignore_ascii_case(&status.environment)))
+    {
+        return Ok(None);
+    }
+
+    let repo = event
+        .repository
+        .ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "repository",
+        })?;
+
+    let repo_name = repo.full_name.unwrap_or(repo.name);
+
+    // the same commit reporting the same state again, e.g. from a re-run, isn't
+    // news.
+    let key = format!("deployment_status:{repo_name}:{}", status.environment);
+    let value = format!("{}:{}", deployment.sha, status.state);
+    if !state.replay() && state.get(&key)?.as_deref() == Some(value.as_str()) {
+        return Ok(None);
+    }
+    state.set(&key, &value);
+
+    let html_url = repo.html_url.ok_or_else(|| RockdoveError::MissingField {
+        event_type: event.kind.clone(),
+        field: "repository.html_url",
+    })?;
+
+    let mut embed = EmbedBuilder::default();
+
+    embed.title(&format!(
+        "[{}] Deployment to {} {}",
+        repo_name,
+        status.environment,
+        match status.state.as_str() {
+            "success" => "succeeded".to_string(),
+            "failure" => "failed".to_string(),
+            "error" => "errored".to_string(),
+            state => format!("is {}", state.replace('_', " ")),
+        },
+    ));
+
+    let environment_url = status.environment_url.filter(|url| !url.is_empty());
+    embed.url(
+        &environment_url
+            .clone()
+            .or_else(|| status.target_url.filter(|url| !url.is_empty()))
+            .unwrap_or_else(|| format!("{html_url}/deployments")),
+    );
+
+    let mut lines = vec![];
+    if let Some(url) = &environment_url {
+        lines.push(format!("**Environment:** {url}"));
+    }
+    lines.push(format!(
+        "**Commit:** [`{}`]({}/commit/{}) on `{}`",
+        &deployment.sha[..7.min(deployment.sha.len())],
+        html_url,
+        deployment.sha,
+        deployment.r#ref,
+    ));
+    if let Some(creator) = &deployment.creator {
+        lines.push(format!("**Deployed by:** {}", creator.login));
+    }
+    if let Some(description) = status.description.filter(|d| !d.is_empty()) {
+        lines.push(description);
+    }
+    embed.description(&lines.join("\n"));
+
+    embed.color(if status.state == "success" {
+        DEPLOYMENT_SUCCEEDED_COLOR
+    } else {
+        DEPLOYMENT_FAILED_COLOR
+    });
+
+    Ok(Some(embed))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{events::Settings, snapshot_test, store::Store, tests::render};
+
+    use yare::parameterized;
+
+    #[parameterized(
+        success = { "success" },
+        failure = { "failure" },
+    )]
+    fn snapshot(event_type: &str) {
+        snapshot_test!("deployment_status", event_type);
+    }
+
+    #[test]
+    fn test_repeated_statuses_collapse() {
+        let settings = Settings::default();
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        assert!(render("deployment_status", "success", &settings, &store).is_some());
+        assert_eq!(
+            render("deployment_status", "success", &settings, &store),
+            None,
+            "the same status for the same commit is only posted once"
+        );
+        assert!(render("deployment_status", "failure", &settings, &store).is_some());
+    }
+
+    #[test]
+    fn test_other_environments_are_ignored() {
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        assert_eq!(
+            render("deployment_status", "preview", &Settings::default(), &store),
+            None,
+            "only production is posted by default"
+        );
+
+        let mut settings = Settings::default();
+        settings.deployment_status.environments = vec!["preview".to_string()];
+        assert!(render("deployment_status", "preview", &settings, &store).is_some());
+    }
+}
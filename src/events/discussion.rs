@@ -1,21 +1,71 @@
 use octocrab::models::webhook_events::{
-    WebhookEvent,
+    WebhookEvent, WebhookEventType,
     payload::{DiscussionWebhookEventAction, DiscussionWebhookEventPayload},
 };
+use serde::Deserialize;
 
 use crate::{
     colors::DISCUSSION_COLOR,
-    embed_builder::EmbedBuilder,
+    embed_builder::{EmbedBuilder, MAX_DESCRIPTION_LENGTH},
     errors::{RockdoveError, RockdoveResult},
+    events::issues::{LabelSettings, describe_label},
 };
 
-// TODO: Create a PR to upstream (octocrab) to add typed events so that we don't
-// need to use `.get()`, `.as_str()`, etc.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    #[serde(flatten)]
+    pub labels: LabelSettings,
+}
+
+#[derive(Deserialize)]
+struct Discussion {
+    number: u64,
+    title: String,
+    html_url: String,
+    body: Option<String>,
+    category: Category,
+}
+
+#[derive(Deserialize)]
+struct Category {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Answer {
+    html_url: String,
+    body: String,
+    user: User,
+}
+
+#[derive(Deserialize)]
+struct User {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct Label {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct CategoryChange {
+    category: Changed<Category>,
+}
+
+#[derive(Deserialize)]
+struct Changed<T> {
+    from: T,
+}
 
 pub fn make_embed(
     event: WebhookEvent,
     specifics: &DiscussionWebhookEventPayload,
+    settings: &Settings,
 ) -> RockdoveResult<Option<EmbedBuilder>> {
+    let discussion: Discussion = parse(&event.kind, Some(&specifics.discussion), "discussion")?;
+
     let repo = event
         .repository
         .ok_or_else(|| RockdoveError::MissingField {
@@ -34,61 +84,60 @@ pub fn make_embed(
             DiscussionWebhookEventAction::Created => "created".to_string(),
             DiscussionWebhookEventAction::Closed => "closed".to_string(),
             DiscussionWebhookEventAction::Reopened => "reopened".to_string(),
+            DiscussionWebhookEventAction::Answered => "answered".to_string(),
+            DiscussionWebhookEventAction::Pinned => "pinned".to_string(),
+            DiscussionWebhookEventAction::CategoryChanged => {
+                format!("moved to {}", discussion.category.name)
+            }
+            DiscussionWebhookEventAction::Labeled | DiscussionWebhookEventAction::Unlabeled => {
+                let label: Option<Label> = specifics
+                    .label
+                    .as_ref()
+                    .map(|label| parse(&event.kind, Some(label), "label"))
+                    .transpose()?;
+                let Some(action) = describe_label(
+                    &event.kind,
+                    label.as_ref().map(|label| label.name.as_str()),
+                    matches!(specifics.action, DiscussionWebhookEventAction::Labeled),
+                    &settings.labels,
+                )?
+                else {
+                    return Ok(None);
+                };
+                action
+            }
             _ => {
                 return Ok(None);
             }
         },
-        specifics
-            .discussion
-            .get("number")
-            .ok_or_else(|| RockdoveError::MissingField {
-                event_type: event.kind.clone(),
-                field: "discussion.number",
-            })?,
-        specifics
-            .discussion
-            .get("title")
-            .ok_or_else(|| RockdoveError::MissingField {
-                event_type: event.kind.clone(),
-                field: "discussion.title",
-            })?
-            .as_str()
-            .ok_or_else(|| RockdoveError::InvalidField {
-                event_type: event.kind.clone(),
-                field: "discussion.title",
-            })?,
+        discussion.number,
+        discussion.title,
     ));
 
-    embed.url(
-        specifics
-            .discussion
-            .get("html_url")
-            .ok_or_else(|| RockdoveError::MissingField {
-                event_type: event.kind.clone(),
-                field: "discussion.html_url",
-            })?
-            .as_str()
-            .ok_or_else(|| RockdoveError::InvalidField {
-                event_type: event.kind.clone(),
-                field: "discussion.html_url",
-            })?,
-    );
-
-    if matches!(specifics.action, DiscussionWebhookEventAction::Created) {
-        embed.description(
-            specifics
-                .discussion
-                .get("body")
-                .ok_or_else(|| RockdoveError::MissingField {
-                    event_type: event.kind.clone(),
-                    field: "discussion.body",
-                })?
-                .as_str()
-                .ok_or_else(|| RockdoveError::InvalidField {
+    embed.url(&discussion.html_url);
+
+    match specifics.action {
+        DiscussionWebhookEventAction::Created => {
+            embed.description(discussion.body.as_ref().ok_or_else(|| {
+                RockdoveError::MissingField {
                     event_type: event.kind.clone(),
                     field: "discussion.body",
-                })?,
-        );
+                }
+            })?);
+        }
+        DiscussionWebhookEventAction::Answered => {
+            let answer: Answer = parse(&event.kind, specifics.answer.as_ref(), "answer")?;
+            let heading = format!("Answered by {}:\n", answer.user.login);
+            let link = format!("\n\n[Jump to answer]({})", answer.html_url);
+            // quote as much as fits without cutting off the link.
+            let room = MAX_DESCRIPTION_LENGTH.saturating_sub(heading.len() + link.len());
+            embed.description(&format!("{heading}{}{link}", excerpt(&answer.body, room)));
+        }
+        DiscussionWebhookEventAction::CategoryChanged => {
+            let change: CategoryChange = parse(&event.kind, specifics.changes.as_ref(), "changes")?;
+            embed.description(&format!("Moved from {}", change.category.from.name));
+        }
+        _ => {}
     }
 
     embed.color(DISCUSSION_COLOR);
@@ -96,18 +145,64 @@ pub fn make_embed(
     Ok(Some(embed))
 }
 
+fn parse<T: for<'de> Deserialize<'de>>(
+    event_type: &WebhookEventType,
+    value: Option<&serde_json::Value>,
+    field: &'static str,
+) -> RockdoveResult<T> {
+    let value = value.ok_or_else(|| RockdoveError::MissingField {
+        event_type: event_type.clone(),
+        field,
+    })?;
+    T::deserialize(value).map_err(|_| RockdoveError::InvalidField {
+        event_type: event_type.clone(),
+        field,
+    })
+}
+
+fn excerpt(body: &str, max_length: usize) -> String {
+    if body.len() > max_length {
+        let end = body.floor_char_boundary(max_length.saturating_sub(3));
+        format!("{}...", body[..end].trim_end())
+    } else {
+        body.to_string()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::snapshot_test;
+    use crate::{
+        embed_builder::MAX_DESCRIPTION_LENGTH, events::Settings, snapshot_test, store::Store,
+        tests::render,
+    };
 
     use yare::parameterized;
 
     #[parameterized(
         created = { "created" },
         closed = { "closed" },
-        reopened = { "reopened" }
+        reopened = { "reopened" },
+        answered = { "answered" },
+        category_changed = { "category_changed" },
+        pinned = { "pinned" },
+        labeled = { "labeled" },
       )]
     fn snapshot(event_type: &str) {
         snapshot_test!("discussion", event_type);
     }
+
+    #[test]
+    fn test_long_answers_keep_the_link() {
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        let embed = render("discussion", "answered_long", &Settings::default(), &store)
+            .expect("answer is rendered");
+        let description = embed["embeds"][0]["description"].as_str().unwrap();
+        assert!(description.len() <= MAX_DESCRIPTION_LENGTH);
+        assert!(
+            description.ends_with(
+                "...\n\n[Jump to answer](https://github.com/catppuccin-rfc/polybar/discussions/19#discussioncomment-10701234)"
+            ),
+            "the answer is cut rather than the link"
+        );
+    }
 }
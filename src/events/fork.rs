@@ -0,0 +1,130 @@
+use octocrab::models::webhook_events::{WebhookEvent, payload::ForkWebhookEventPayload};
+use serde::Deserialize;
+
+use crate::{
+    colors::FORK_COLOR,
+    embed_builder::EmbedBuilder,
+    errors::{RockdoveError, RockdoveResult},
+    events::{
+        State,
+        star::{Mode, crossed_milestone},
+    },
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Whether to post every event or only milestones.
+    pub mode: Mode,
+    /// The counts that are worth celebrating.
+    pub milestones: Vec<u32>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            mode: Mode::Every,
+            milestones: vec![50, 100, 250, 500, 1000],
+        }
+    }
+}
+
+pub fn make_embed(
+    event: WebhookEvent,
+    specifics: &ForkWebhookEventPayload,
+    settings: &Settings,
+    state: &State,
+) -> RockdoveResult<Option<EmbedBuilder>> {
+    let repo = event
+        .repository
+        .ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "repository",
+        })?;
+
+    let mut embed = EmbedBuilder::default();
+
+    let repo_name = repo.full_name.unwrap_or(repo.name);
+
+    match settings.mode {
+        Mode::Every => {
+            embed.title(&format!(
+                "[{}] Forked to {}",
+                repo_name,
+                specifics
+                    .forkee
+                    .full_name
+                    .as_ref()
+                    .unwrap_or(&specifics.forkee.name),
+            ));
+            embed.url(
+                specifics
+                    .forkee
+                    .html_url
+                    .as_ref()
+                    .ok_or_else(|| RockdoveError::MissingField {
+                        event_type: event.kind.clone(),
+                        field: "forkee.html_url",
+                    })?
+                    .as_str(),
+            );
+        }
+        Mode::Milestones => {
+            let forks = repo
+                .forks_count
+                .ok_or_else(|| RockdoveError::MissingField {
+                    event_type: event.kind.clone(),
+                    field: "repository.forks_count",
+                })?;
+            let Some(milestone) = crossed_milestone(
+                state,
+                &format!("fork_milestone:{repo_name}"),
+                forks,
+                &settings.milestones,
+            )?
+            else {
+                return Ok(None);
+            };
+            embed.title(&format!("[{repo_name}] Reached {milestone} forks"));
+            embed.url(&format!(
+                "{}/forks",
+                repo.html_url.ok_or_else(|| RockdoveError::MissingField {
+                    event_type: event.kind.clone(),
+                    field: "repository.html_url",
+                })?
+            ));
+        }
+    }
+
+    embed.color(FORK_COLOR);
+
+    Ok(Some(embed))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        events::{Settings, star::Mode},
+        snapshot_test,
+        store::Store,
+        tests::render,
+    };
+
+    #[test]
+    fn created() {
+        snapshot_test!("fork", "created");
+    }
+
+    #[test]
+    fn milestone() {
+        let mut settings = Settings::default();
+        settings.fork.mode = Mode::Milestones;
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        assert_eq!(
+            render("fork", "created", &settings, &store),
+            None,
+            "the first fork seen only records the count"
+        );
+        snapshot_test!("fork", "milestone", &settings, &store);
+    }
+}
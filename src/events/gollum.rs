@@ -0,0 +1,96 @@
+use octocrab::models::webhook_events::{WebhookEvent, payload::GollumWebhookEventPayload};
+use serde::Deserialize;
+
+use crate::{
+    colors::WIKI_COLOR,
+    embed_builder::{EmbedBuilder, MAX_DESCRIPTION_LENGTH, fit_lines},
+    errors::{RockdoveError, RockdoveResult},
+};
+
+/// The most pages listed in a single embed.
+const MAX_PAGES: usize = 10;
+
+#[derive(Debug, Deserialize)]
+struct Page {
+    title: String,
+    action: String,
+    html_url: String,
+    summary: Option<String>,
+}
+
+pub fn make_embed(
+    event: WebhookEvent,
+    specifics: &GollumWebhookEventPayload,
+) -> RockdoveResult<Option<EmbedBuilder>> {
+    let pages = specifics
+        .pages
+        .iter()
+        .map(Page::deserialize)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| RockdoveError::InvalidField {
+            event_type: event.kind.clone(),
+            field: "pages",
+        })?;
+
+    let repo = event
+        .repository
+        .ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "repository",
+        })?;
+
+    let mut embed = EmbedBuilder::default();
+
+    let repo_name = repo.full_name.unwrap_or(repo.name);
+
+    match pages.as_slice() {
+        [] => return Ok(None),
+        [page] => {
+            embed.title(&format!(
+                "[{repo_name}] Wiki page {}: {}",
+                page.action, page.title
+            ));
+            embed.url(&page.html_url);
+            if let Some(summary) = &page.summary {
+                embed.description(summary);
+            }
+        }
+        pages => {
+            embed.title(&format!("[{repo_name}] {} wiki pages updated", pages.len()));
+            let html_url = repo.html_url.ok_or_else(|| RockdoveError::MissingField {
+                event_type: event.kind.clone(),
+                field: "repository.html_url",
+            })?;
+            embed.url(&format!("{html_url}/wiki"));
+            let lines: Vec<_> = pages.iter().map(page_line).collect();
+            embed.description(&fit_lines(&lines, MAX_PAGES, MAX_DESCRIPTION_LENGTH));
+        }
+    }
+
+    embed.color(WIKI_COLOR);
+
+    Ok(Some(embed))
+}
+
+fn page_line(page: &Page) -> String {
+    let mut action = page.action.clone();
+    if let Some(first) = action.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+    format!("{action} [{}]({})", page.title, page.html_url)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snapshot_test;
+
+    use yare::parameterized;
+
+    #[parameterized(
+        created = { "created" },
+        many_pages = { "many_pages" },
+    )]
+    fn snapshot(event_type: &str) {
+        snapshot_test!("gollum", event_type);
+    }
+}
@@ -1,22 +1,32 @@
+use chrono::{DateTime, Utc};
 use octocrab::models::webhook_events::{
     WebhookEvent,
     payload::{IssueCommentWebhookEventAction, IssueCommentWebhookEventPayload},
 };
+use serde::Deserialize;
 
 use crate::{
-    colors::{ISSUE_COLOR, PULL_REQUEST_COLOR},
-    embed_builder::EmbedBuilder,
+    colors::{COMMENT_DELETED_COLOR, ISSUE_COLOR, PULL_REQUEST_COLOR},
+    embed_builder::{EmbedBuilder, MAX_DESCRIPTION_LENGTH, fit_lines},
     errors::{RockdoveError, RockdoveResult},
 };
 
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Post a diff when a comment is edited.
+    pub edits: bool,
+}
+
+/// The most changed lines shown in an edit's diff.
+const MAX_DIFF_LINES: usize = 10;
+const MAX_DIFF_LINE_LENGTH: usize = 72;
+
 pub fn make_embed(
     event: WebhookEvent,
     specifics: &IssueCommentWebhookEventPayload,
+    settings: &Settings,
 ) -> RockdoveResult<Option<EmbedBuilder>> {
-    if !matches!(specifics.action, IssueCommentWebhookEventAction::Created) {
-        return Ok(None);
-    }
-
     let repo = event
         .repository
         .ok_or_else(|| RockdoveError::MissingField {
@@ -32,38 +42,151 @@ pub fn make_embed(
     } else {
         "issue"
     };
+    let on = format!(
+        "{} #{}: {}",
+        target, specifics.issue.number, specifics.issue.title
+    );
+    let body = specifics.comment.body.as_deref().unwrap_or_default();
 
-    embed.title(&format!(
-        "[{}] New comment on {} #{}: {}",
-        repo_name, target, specifics.issue.number, specifics.issue.title,
-    ));
+    match specifics.action {
+        IssueCommentWebhookEventAction::Created => {
+            embed.title(&format!("[{repo_name}] New comment on {on}"));
+            if !body.is_empty() {
+                embed.description(body);
+            }
+        }
+        IssueCommentWebhookEventAction::Edited => {
+            if !settings.edits {
+                return Ok(None);
+            }
+            let before = specifics
+                .changes
+                .as_ref()
+                .ok_or_else(|| RockdoveError::MissingField {
+                    event_type: event.kind.clone(),
+                    field: "changes.body",
+                })?;
+            let Some(diff) = describe_edit(&before.body.from, body) else {
+                return Ok(None);
+            };
+            embed.title(&format!("[{repo_name}] Comment edited on {on}"));
+            embed.description(&diff);
+        }
+        IssueCommentWebhookEventAction::Deleted => {
+            embed.title(&format!("[{repo_name}] Comment deleted on {on}"));
+            embed.description(&describe_deleted(
+                &specifics.comment.user.login,
+                specifics.comment.created_at,
+                body,
+            ));
+        }
+        _ => return Ok(None),
+    }
 
     embed.url(specifics.comment.html_url.as_str());
 
-    if let Some(ref body) = specifics.comment.body {
-        embed.description(body);
-    }
-
-    embed.color(if specifics.issue.pull_request.is_some() {
-        PULL_REQUEST_COLOR
-    } else {
-        ISSUE_COLOR
+    embed.color(match specifics.action {
+        IssueCommentWebhookEventAction::Deleted => COMMENT_DELETED_COLOR,
+        _ if specifics.issue.pull_request.is_some() => PULL_REQUEST_COLOR,
+        _ => ISSUE_COLOR,
     });
 
     Ok(Some(embed))
 }
 
+/// A diff of the lines that changed in an edited comment, or `None` if none
+/// did.
+pub fn describe_edit(before: &str, after: &str) -> Option<String> {
+    let lines = diff(before, after);
+    if lines.is_empty() {
+        return None;
+    }
+    // longer than any run of backticks in the comment, so that it can't close
+    // the block early.
+    let longest_run = lines
+        .iter()
+        .flat_map(|line| line.split(|c| c != '`'))
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    let room = MAX_DESCRIPTION_LENGTH - format!("{fence}diff\n\n{fence}").len();
+    Some(format!(
+        "{fence}diff\n{}\n{fence}",
+        fit_lines(&lines, MAX_DIFF_LINES, room),
+    ))
+}
+
+/// Who posted a deleted comment and when, followed by what it said.
+pub fn describe_deleted(author: &str, posted: DateTime<Utc>, body: &str) -> String {
+    format!(
+        "**Author:** {author}\n**Posted:** {}\n\n{body}",
+        posted.format("%Y-%m-%d %H:%M UTC"),
+    )
+}
+
+/// The lines that changed between two versions of a comment, leaving out the
+/// unchanged lines at the start and end.
+fn diff(before: &str, after: &str) -> Vec<String> {
+    let before: Vec<_> = before.lines().collect();
+    let after: Vec<_> = after.lines().collect();
+
+    let prefix = before
+        .iter()
+        .zip(&after)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = before[prefix..]
+        .iter()
+        .rev()
+        .zip(after[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let removed = before[prefix..before.len() - suffix]
+        .iter()
+        .map(|line| diff_line('-', line));
+    let added = after[prefix..after.len() - suffix]
+        .iter()
+        .map(|line| diff_line('+', line));
+    removed.chain(added).collect()
+}
+
+fn diff_line(sign: char, line: &str) -> String {
+    if line.chars().count() > MAX_DIFF_LINE_LENGTH {
+        let truncated: String = line.chars().take(MAX_DIFF_LINE_LENGTH - 3).collect();
+        format!("{sign} {truncated}...")
+    } else {
+        format!("{sign} {line}")
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::snapshot_test;
+    use crate::{events::Settings, snapshot_test};
 
     use yare::parameterized;
 
     #[parameterized(
         created = { "created" },
         created_on_pull_request = { "created_on_pull_request" },
+        deleted = { "deleted" },
       )]
     fn snapshot(event_type: &str) {
         snapshot_test!("issue_comment", event_type);
     }
+
+    #[test]
+    fn test_edits_are_ignored_by_default() {
+        let store = crate::store::Store::open_in_memory().expect("in-memory store opens");
+        let embed = crate::tests::render("issue_comment", "edited", &Settings::default(), &store);
+        assert_eq!(embed, None, "edits should only be posted when enabled");
+    }
+
+    #[test]
+    fn test_edits() {
+        let mut settings = Settings::default();
+        settings.issue_comment.edits = true;
+        snapshot_test!("issue_comment", "edited", &settings);
+    }
 }
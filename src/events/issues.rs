@@ -1,20 +1,117 @@
 use octocrab::models::{
+    Milestone,
     issues::IssueStateReason,
     webhook_events::{
-        WebhookEvent,
+        WebhookEvent, WebhookEventType,
         payload::{IssuesWebhookEventAction, IssuesWebhookEventPayload},
     },
 };
 
+use serde::Deserialize;
+
 use crate::{
     colors::ISSUE_COLOR,
     embed_builder::EmbedBuilder,
     errors::{RockdoveError, RockdoveResult},
 };
 
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    #[serde(flatten)]
+    pub labels: LabelSettings,
+}
+
+/// The `labels` setting shared by issues, pull requests and discussions.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct LabelSettings {
+    /// The labels that are posted when added or removed, ignoring case. An
+    /// empty list posts every label.
+    pub labels: Vec<String>,
+}
+
+impl Default for LabelSettings {
+    fn default() -> Self {
+        Self {
+            labels: [
+                "security",
+                "good first issue",
+                "port request",
+                "help wanted",
+            ]
+            .map(ToString::to_string)
+            .to_vec(),
+        }
+    }
+}
+
+/// Describes a label being added or removed, or `None` if the label isn't
+/// allowed.
+pub fn describe_label(
+    event_type: &WebhookEventType,
+    label: Option<&str>,
+    added: bool,
+    settings: &LabelSettings,
+) -> RockdoveResult<Option<String>> {
+    let label = label.ok_or_else(|| RockdoveError::MissingField {
+        event_type: event_type.clone(),
+        field: "label",
+    })?;
+    let allowed = &settings.labels;
+    if !allowed.is_empty() && !allowed.iter().any(|a| a.eq_ignore_ascii_case(label)) {
+        return Ok(None);
+    }
+    Ok(Some(if added {
+        format!("labeled {label}")
+    } else {
+        format!("unlabeled {label}")
+    }))
+}
+
+pub fn describe_milestone(
+    event_type: &WebhookEventType,
+    milestone: Option<&Milestone>,
+    added: bool,
+) -> RockdoveResult<String> {
+    let milestone = milestone.ok_or_else(|| RockdoveError::MissingField {
+        event_type: event_type.clone(),
+        field: "milestone",
+    })?;
+    Ok(if added {
+        format!("added to milestone {}", milestone.title)
+    } else {
+        format!("removed from milestone {}", milestone.title)
+    })
+}
+
+/// The parts of a transfer that octocrab doesn't keep.
+#[derive(Deserialize)]
+struct Transfer {
+    changes: TransferChanges,
+}
+
+#[derive(Deserialize)]
+struct TransferChanges {
+    new_issue: TransferredIssue,
+    new_repository: TransferredRepository,
+}
+
+#[derive(Deserialize)]
+struct TransferredIssue {
+    html_url: String,
+}
+
+#[derive(Deserialize)]
+struct TransferredRepository {
+    full_name: String,
+}
+
 pub fn make_embed(
     event: WebhookEvent,
     specifics: &IssuesWebhookEventPayload,
+    settings: &Settings,
+    payload: &serde_json::Value,
 ) -> RockdoveResult<Option<EmbedBuilder>> {
     let repo = event
         .repository
@@ -27,9 +124,9 @@ pub fn make_embed(
 
     let repo_name = repo.full_name.unwrap_or(repo.name);
 
-    embed.title(&format!(
-        "[{}] Issue {}: #{} {}",
-        repo_name,
+    let mut url = specifics.issue.html_url.to_string();
+
+    let action =
         match specifics.action {
             IssuesWebhookEventAction::Assigned => {
                 let assignee = specifics.issue.assignee.as_ref().ok_or_else(|| {
@@ -40,34 +137,73 @@ pub fn make_embed(
                 })?;
                 format!("assigned to {}", assignee.login)
             }
-            IssuesWebhookEventAction::Closed => {
-                specifics.issue.state_reason.as_ref().map_or_else(
-                    || "closed".to_string(),
-                    |state_reason| match state_reason {
-                        IssueStateReason::NotPlanned => "closed as not planned".to_string(),
-                        IssueStateReason::Reopened => "reopened".to_string(),
-                        IssueStateReason::Duplicate => "closed as duplicate".to_string(),
-                        _ => "closed".to_string(),
-                    },
-                )
+            IssuesWebhookEventAction::Unassigned => {
+                let assignee =
+                    specifics
+                        .assignee
+                        .as_ref()
+                        .ok_or_else(|| RockdoveError::MissingField {
+                            event_type: event.kind.clone(),
+                            field: "assignee",
+                        })?;
+                format!("unassigned from {}", assignee.login)
             }
+            IssuesWebhookEventAction::Closed => specifics.issue.state_reason.as_ref().map_or_else(
+                || "closed".to_string(),
+                |state_reason| match state_reason {
+                    IssueStateReason::NotPlanned => "closed as not planned".to_string(),
+                    IssueStateReason::Reopened => "reopened".to_string(),
+                    IssueStateReason::Duplicate => "closed as duplicate".to_string(),
+                    _ => "closed".to_string(),
+                },
+            ),
             IssuesWebhookEventAction::Locked => "locked".to_string(),
             IssuesWebhookEventAction::Opened => "opened".to_string(),
             IssuesWebhookEventAction::Pinned => "pinned".to_string(),
             IssuesWebhookEventAction::Reopened => "reopened".to_string(),
-            // TODO: this would be nice
-            // IssuesWebhookEventAction::Transferred => {
-            //     todo!()
-            // }
+            IssuesWebhookEventAction::Labeled | IssuesWebhookEventAction::Unlabeled => {
+                let Some(action) = describe_label(
+                    &event.kind,
+                    specifics.label.as_ref().map(|label| label.name.as_str()),
+                    matches!(specifics.action, IssuesWebhookEventAction::Labeled),
+                    &settings.labels,
+                )?
+                else {
+                    return Ok(None);
+                };
+                action
+            }
+            IssuesWebhookEventAction::Milestoned | IssuesWebhookEventAction::Demilestoned => {
+                describe_milestone(
+                    &event.kind,
+                    specifics.milestone.as_ref(),
+                    matches!(specifics.action, IssuesWebhookEventAction::Milestoned),
+                )?
+            }
+            IssuesWebhookEventAction::Transferred => {
+                let transfer =
+                    Transfer::deserialize(payload).map_err(|_| RockdoveError::InvalidField {
+                        event_type: event.kind.clone(),
+                        field: "changes",
+                    })?;
+                // the old issue is gone, so link to where it went.
+                url = transfer.changes.new_issue.html_url;
+                format!(
+                    "transferred to {}",
+                    transfer.changes.new_repository.full_name
+                )
+            }
             _ => {
                 return Ok(None);
             }
-        },
-        specifics.issue.number,
-        specifics.issue.title,
+        };
+
+    embed.title(&format!(
+        "[{}] Issue {}: #{} {}",
+        repo_name, action, specifics.issue.number, specifics.issue.title,
     ));
 
-    embed.url(specifics.issue.html_url.as_str());
+    embed.url(&url);
 
     if matches!(specifics.action, IssuesWebhookEventAction::Opened)
         && let Some(ref body) = specifics.issue.body
@@ -82,7 +218,7 @@ pub fn make_embed(
 
 #[cfg(test)]
 mod tests {
-    use crate::snapshot_test;
+    use crate::{events::Settings, snapshot_test, store::Store, tests::render};
 
     use yare::parameterized;
 
@@ -91,9 +227,26 @@ mod tests {
         closed = { "closed" },
         closed_as_duplicate = { "closed_as_duplicate" },
         closed_as_not_planned = { "closed_as_not_planned" },
-        reopened = { "reopened" }
+        reopened = { "reopened" },
+        labeled = { "labeled" },
+        unlabeled = { "unlabeled" },
+        milestoned = { "milestoned" },
+        demilestoned = { "demilestoned" },
+        unassigned = { "unassigned" },
+        transferred = { "transferred" },
       )]
     fn snapshot(event_type: &str) {
         snapshot_test!("issues", event_type);
     }
+
+    #[test]
+    fn test_labels_outside_the_allowlist_are_ignored() {
+        let settings = Settings::default();
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        assert_eq!(
+            render("issues", "labeled_ignored", &settings, &store),
+            None,
+            "wontfix isn't in the default allowlist"
+        );
+    }
 }
@@ -0,0 +1,44 @@
+use octocrab::models::webhook_events::{
+    WebhookEvent,
+    payload::{MetaWebhookEventAction, MetaWebhookEventPayload},
+};
+
+use crate::{
+    colors::HOOK_DELETED_COLOR, embed_builder::EmbedBuilder, errors::RockdoveResult,
+    events::ping::hook_scope,
+};
+
+pub fn make_embed(
+    event: WebhookEvent,
+    specifics: &MetaWebhookEventPayload,
+) -> RockdoveResult<Option<EmbedBuilder>> {
+    if !matches!(specifics.action, MetaWebhookEventAction::Deleted) {
+        return Ok(None);
+    }
+
+    let (scope, settings_url) = hook_scope(event)?;
+
+    let mut embed = EmbedBuilder::default();
+
+    embed.title(&format!("[{scope}] Webhook {} deleted", specifics.hook_id));
+
+    embed.url(&settings_url);
+
+    embed.description(&format!(
+        "rockdove will no longer receive events from {scope}."
+    ));
+
+    embed.color(HOOK_DELETED_COLOR);
+
+    Ok(Some(embed))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snapshot_test;
+
+    #[test]
+    fn deleted() {
+        snapshot_test!("meta", "deleted");
+    }
+}
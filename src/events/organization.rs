@@ -0,0 +1,133 @@
+use octocrab::models::webhook_events::{
+    WebhookEvent,
+    payload::{OrganizationWebhookEventAction, OrganizationWebhookEventPayload},
+};
+use serde::Deserialize;
+
+use crate::{
+    colors::MEMBERSHIP_COLOR,
+    embed_builder::EmbedBuilder,
+    errors::{RockdoveError, RockdoveResult},
+};
+
+/// Read from the raw payload, since octocrab doesn't keep `invitation`.
+#[derive(Deserialize)]
+struct Payload {
+    membership: Option<Membership>,
+    invitation: Option<Invitation>,
+}
+
+#[derive(Deserialize)]
+struct Membership {
+    role: String,
+    user: User,
+}
+
+#[derive(Deserialize)]
+struct Invitation {
+    /// Missing for invitations sent by email.
+    login: Option<String>,
+    role: String,
+}
+
+#[derive(Deserialize)]
+struct User {
+    login: String,
+}
+
+pub fn make_embed(
+    event: WebhookEvent,
+    specifics: &OrganizationWebhookEventPayload,
+    payload: &serde_json::Value,
+) -> RockdoveResult<Option<EmbedBuilder>> {
+    if !matches!(
+        specifics.action,
+        OrganizationWebhookEventAction::MemberInvited
+            | OrganizationWebhookEventAction::MemberAdded
+            | OrganizationWebhookEventAction::MemberRemoved
+    ) {
+        return Ok(None);
+    }
+
+    let org = event
+        .organization
+        .ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "organization",
+        })?;
+
+    let payload = Payload::deserialize(payload).map_err(|_| RockdoveError::InvalidField {
+        event_type: event.kind.clone(),
+        field: "membership",
+    })?;
+
+    let mut embed = EmbedBuilder::default();
+
+    let (title, role) = if matches!(
+        specifics.action,
+        OrganizationWebhookEventAction::MemberInvited
+    ) {
+        let invitation = payload
+            .invitation
+            .ok_or_else(|| RockdoveError::MissingField {
+                event_type: event.kind.clone(),
+                field: "invitation",
+            })?;
+        (
+            format!(
+                "{} invited to the organization",
+                // the email address isn't anyone else's business.
+                invitation.login.as_deref().unwrap_or("someone"),
+            ),
+            invitation.role,
+        )
+    } else {
+        let membership = payload
+            .membership
+            .ok_or_else(|| RockdoveError::MissingField {
+                event_type: event.kind.clone(),
+                field: "membership",
+            })?;
+        (
+            format!(
+                "{} {} the organization",
+                membership.user.login,
+                if matches!(
+                    specifics.action,
+                    OrganizationWebhookEventAction::MemberAdded
+                ) {
+                    "added to"
+                } else {
+                    "removed from"
+                },
+            ),
+            membership.role,
+        )
+    };
+
+    embed.title(&format!("[{}] {}", org.login, title));
+
+    embed.url(&format!("https://github.com/orgs/{}/people", org.login));
+
+    embed.description(&format!("**Role:** {}", role.replace('_', " ")));
+
+    embed.color(MEMBERSHIP_COLOR);
+
+    Ok(Some(embed))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snapshot_test;
+
+    use yare::parameterized;
+
+    #[parameterized(
+        member_invited = { "member_invited" },
+        member_added = { "member_added" },
+        member_removed = { "member_removed" },
+      )]
+    fn snapshot(event_type: &str) {
+        snapshot_test!("organization", event_type);
+    }
+}
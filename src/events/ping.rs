@@ -0,0 +1,102 @@
+use octocrab::models::webhook_events::{WebhookEvent, payload::PingWebhookEventPayload};
+
+use crate::{
+    colors::HOOK_CONNECTED_COLOR,
+    embed_builder::EmbedBuilder,
+    errors::{RockdoveError, RockdoveResult},
+    events::RENDERED_EVENTS,
+};
+
+pub fn make_embed(
+    event: WebhookEvent,
+    specifics: &PingWebhookEventPayload,
+) -> RockdoveResult<Option<EmbedBuilder>> {
+    let hook = specifics
+        .hook
+        .as_ref()
+        .ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "hook",
+        })?;
+
+    let (scope, settings_url) = hook_scope(event)?;
+
+    let mut embed = EmbedBuilder::default();
+
+    embed.title(&format!("[{scope}] rockdove connected"));
+
+    embed.url(&format!("{settings_url}/{}", hook.id));
+
+    let subscribed: Vec<String> = hook
+        .events
+        .iter()
+        .filter_map(|kind| serde_json::to_value(kind).ok()?.as_str().map(String::from))
+        .collect();
+    let mut lines = vec![format!("**Hook:** {}", hook.id)];
+    if subscribed.iter().any(|event| event == "*") {
+        lines.push("**Subscribed to:** every event".to_string());
+        lines.push(format!("**Rendered:** {}", RENDERED_EVENTS.join(", ")));
+    } else {
+        let (rendered, ignored): (Vec<&str>, Vec<&str>) = subscribed
+            .iter()
+            .map(String::as_str)
+            .partition(|event| RENDERED_EVENTS.contains(event));
+        lines.push(format!("**Subscribed to:** {}", subscribed.join(", ")));
+        lines.push(format!("**Rendered:** {}", list(&rendered)));
+        lines.push(format!("**Ignored:** {}", list(&ignored)));
+    }
+    embed.description(&lines.join("\n"));
+
+    embed.color(HOOK_CONNECTED_COLOR);
+
+    Ok(Some(embed))
+}
+
+/// Where the hook lives and the url of its settings, for both organization
+/// and repository hooks.
+pub fn hook_scope(event: WebhookEvent) -> RockdoveResult<(String, String)> {
+    if let Some(repo) = event.repository {
+        let html_url = repo.html_url.ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "repository.html_url",
+        })?;
+        return Ok((
+            repo.full_name.unwrap_or(repo.name),
+            format!("{html_url}/settings/hooks"),
+        ));
+    }
+    let org = event
+        .organization
+        .ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "(repository|organization)",
+        })?;
+    let settings_url = format!(
+        "https://github.com/organizations/{}/settings/hooks",
+        org.login
+    );
+    Ok((org.login, settings_url))
+}
+
+fn list(events: &[&str]) -> String {
+    if events.is_empty() {
+        "*none*".to_string()
+    } else {
+        events.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snapshot_test;
+
+    use yare::parameterized;
+
+    #[parameterized(
+        organization = { "organization" },
+        wildcard = { "wildcard" },
+      )]
+    fn snapshot(event_type: &str) {
+        snapshot_test!("ping", event_type);
+    }
+}
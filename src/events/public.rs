@@ -0,0 +1,46 @@
+use octocrab::models::webhook_events::WebhookEvent;
+
+use crate::{
+    colors::REPO_COLOR,
+    embed_builder::EmbedBuilder,
+    errors::{RockdoveError, RockdoveResult},
+};
+
+pub fn make_embed(event: WebhookEvent) -> RockdoveResult<Option<EmbedBuilder>> {
+    let repo = event
+        .repository
+        .ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "repository",
+        })?;
+
+    let mut embed = EmbedBuilder::default();
+
+    embed.title(&format!(
+        "[{}] Repository made public",
+        repo.full_name.unwrap_or(repo.name)
+    ));
+
+    embed.url(
+        repo.html_url
+            .ok_or_else(|| RockdoveError::MissingField {
+                event_type: event.kind.clone(),
+                field: "repository.html_url",
+            })?
+            .as_str(),
+    );
+
+    embed.color(REPO_COLOR);
+
+    Ok(Some(embed))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snapshot_test;
+
+    #[test]
+    fn public() {
+        snapshot_test!("public", "public");
+    }
+}
@@ -1,17 +1,66 @@
-use octocrab::models::webhook_events::{
-    WebhookEvent,
-    payload::{PullRequestWebhookEventAction, PullRequestWebhookEventPayload},
+use octocrab::models::{
+    Repository,
+    webhook_events::{
+        WebhookEvent, WebhookEventType,
+        payload::{PullRequestWebhookEventAction, PullRequestWebhookEventPayload},
+    },
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{
     colors::PULL_REQUEST_COLOR,
     embed_builder::EmbedBuilder,
     errors::{RockdoveError, RockdoveResult},
+    events::{
+        State,
+        issues::{LabelSettings, describe_label, describe_milestone},
+    },
+    store,
 };
 
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    #[serde(flatten)]
+    pub labels: LabelSettings,
+    /// Don't post anything about draft pull requests until they're ready for
+    /// review, and show the description then instead.
+    pub skip_drafts: bool,
+    /// Post when new commits are pushed to a pull request.
+    pub synchronize: bool,
+    /// The least time between two posts about new commits on the same pull
+    /// request. Pushes in between aren't posted, but their commits are counted
+    /// in the next post, so the last push of a burst only shows up once
+    /// something else is pushed after the interval.
+    pub synchronize_interval_secs: i64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            labels: LabelSettings::default(),
+            skip_drafts: false,
+            synchronize: false,
+            synchronize_interval_secs: 10 * 60,
+        }
+    }
+}
+
+/// What we remember about a pull request's commits, to throttle `synchronize`
+/// posts.
+#[derive(Serialize, Deserialize)]
+struct Pushed {
+    /// When we last posted about it, or when it was opened.
+    posted_at: i64,
+    /// How many commits it had at the time.
+    commits: u64,
+}
+
 pub fn make_embed(
     event: WebhookEvent,
     specifics: &PullRequestWebhookEventPayload,
+    settings: &Settings,
+    state: &State,
 ) -> RockdoveResult<Option<EmbedBuilder>> {
     let repo = event
         .repository
@@ -24,65 +73,14 @@ pub fn make_embed(
 
     let repo_name = repo.full_name.as_ref().unwrap_or(&repo.name);
 
+    let Some(action) = describe_action(&event.kind, &repo, specifics, settings, state)? else {
+        return Ok(None);
+    };
+
     embed.title(&format!(
         "[{}] Pull request {}: #{} {}",
         repo_name,
-        match specifics.action {
-            PullRequestWebhookEventAction::Assigned => {
-                let assignee =
-                    specifics
-                        .assignee
-                        .as_ref()
-                        .ok_or_else(|| RockdoveError::MissingField {
-                            event_type: event.kind.clone(),
-                            field: "assignee",
-                        })?;
-                format!("assigned to {}", assignee.login)
-            }
-            PullRequestWebhookEventAction::Closed => {
-                if specifics.pull_request.merged_at.is_some() {
-                    "merged".to_string()
-                } else {
-                    "closed".to_string()
-                }
-            }
-            PullRequestWebhookEventAction::Locked => "locked".to_string(),
-            PullRequestWebhookEventAction::Opened => "opened".to_string(),
-            PullRequestWebhookEventAction::ReadyForReview => "ready for review".to_string(),
-            PullRequestWebhookEventAction::Reopened => "reopened".to_string(),
-            PullRequestWebhookEventAction::ReviewRequested => {
-                // userstyles is a monorepo with codeowners so generates a lot of noise in this case
-                if repo.name == "userstyles" {
-                    return Ok(None);
-                }
-
-                let mut reviewer_names = vec![];
-                reviewer_names.extend(
-                    specifics
-                        .requested_reviewer
-                        .as_ref()
-                        .map(|user| user.login.as_str()),
-                );
-                reviewer_names.extend(
-                    specifics
-                        .requested_team
-                        .as_ref()
-                        .map(|team| team.name.as_str()),
-                );
-
-                if reviewer_names.is_empty() {
-                    return Err(RockdoveError::MissingField {
-                        event_type: event.kind.clone(),
-                        field: "(requested_reviewer|requested_team)",
-                    });
-                }
-
-                format!("review requested from {}", reviewer_names.join(", "))
-            }
-            _ => {
-                return Ok(None);
-            }
-        },
+        action,
         specifics.number,
         specifics
             .pull_request
@@ -94,7 +92,7 @@ pub fn make_embed(
             })?
     ));
 
-    embed.url(
+    let html_url =
         specifics
             .pull_request
             .html_url
@@ -102,11 +100,23 @@ pub fn make_embed(
             .ok_or_else(|| RockdoveError::MissingField {
                 event_type: event.kind.clone(),
                 field: "pull_request.html_url",
-            })?
-            .as_str(),
+            })?;
+    embed.url(
+        &match (&specifics.action, &specifics.before, &specifics.after) {
+            (PullRequestWebhookEventAction::Synchronize, Some(before), Some(after)) => {
+                format!("{html_url}/files/{before}..{after}")
+            }
+            _ => html_url.to_string(),
+        },
     );
 
-    if matches!(specifics.action, PullRequestWebhookEventAction::Opened)
+    // with drafts skipped, nobody has seen the description until it's ready.
+    let introduced = if settings.skip_drafts {
+        PullRequestWebhookEventAction::ReadyForReview
+    } else {
+        PullRequestWebhookEventAction::Opened
+    };
+    if specifics.action == introduced
         && let Some(ref body) = specifics.pull_request.body
     {
         embed.description(body);
@@ -117,9 +127,175 @@ pub fn make_embed(
     Ok(Some(embed))
 }
 
+/// What happened to the pull request, or `None` if it isn't worth posting.
+fn describe_action(
+    event_type: &WebhookEventType,
+    repo: &Repository,
+    specifics: &PullRequestWebhookEventPayload,
+    settings: &Settings,
+    state: &State,
+) -> RockdoveResult<Option<String>> {
+    let repo_name = repo.full_name.as_ref().unwrap_or(&repo.name);
+    let key = format!("pull_request_commits:{repo_name}#{}", specifics.number);
+    if settings.synchronize
+        && matches!(
+            specifics.action,
+            PullRequestWebhookEventAction::Opened | PullRequestWebhookEventAction::Reopened
+        )
+    {
+        remember_commits(state, &key, specifics.pull_request.commits.unwrap_or(0))?;
+    }
+
+    if settings.skip_drafts
+        && specifics.pull_request.draft == Some(true)
+        && specifics.action != PullRequestWebhookEventAction::ReadyForReview
+    {
+        return Ok(None);
+    }
+
+    Ok(Some(match specifics.action {
+        PullRequestWebhookEventAction::Assigned | PullRequestWebhookEventAction::Unassigned => {
+            let assignee =
+                specifics
+                    .assignee
+                    .as_ref()
+                    .ok_or_else(|| RockdoveError::MissingField {
+                        event_type: event_type.clone(),
+                        field: "assignee",
+                    })?;
+            if matches!(specifics.action, PullRequestWebhookEventAction::Assigned) {
+                format!("assigned to {}", assignee.login)
+            } else {
+                format!("unassigned from {}", assignee.login)
+            }
+        }
+        PullRequestWebhookEventAction::Labeled | PullRequestWebhookEventAction::Unlabeled => {
+            let Some(action) = describe_label(
+                event_type,
+                specifics.label.as_ref().map(|label| label.name.as_str()),
+                matches!(specifics.action, PullRequestWebhookEventAction::Labeled),
+                &settings.labels,
+            )?
+            else {
+                return Ok(None);
+            };
+            action
+        }
+        PullRequestWebhookEventAction::Milestoned | PullRequestWebhookEventAction::Demilestoned => {
+            describe_milestone(
+                event_type,
+                specifics.milestone.as_ref(),
+                matches!(specifics.action, PullRequestWebhookEventAction::Milestoned),
+            )?
+        }
+        PullRequestWebhookEventAction::Closed => {
+            if specifics.pull_request.merged_at.is_some() {
+                "merged".to_string()
+            } else {
+                "closed".to_string()
+            }
+        }
+        PullRequestWebhookEventAction::Locked => "locked".to_string(),
+        PullRequestWebhookEventAction::Opened => "opened".to_string(),
+        PullRequestWebhookEventAction::ConvertedToDraft => "converted to draft".to_string(),
+        PullRequestWebhookEventAction::Synchronize => {
+            return describe_push(
+                state,
+                &key,
+                specifics.pull_request.commits.unwrap_or(0),
+                settings,
+            );
+        }
+        PullRequestWebhookEventAction::ReadyForReview => "ready for review".to_string(),
+        PullRequestWebhookEventAction::Reopened => "reopened".to_string(),
+        PullRequestWebhookEventAction::ReviewRequested => {
+            // userstyles is a monorepo with codeowners so generates a lot of noise in this case
+            if repo.name == "userstyles" {
+                return Ok(None);
+            }
+
+            let mut reviewer_names = vec![];
+            reviewer_names.extend(
+                specifics
+                    .requested_reviewer
+                    .as_ref()
+                    .map(|user| user.login.as_str()),
+            );
+            reviewer_names.extend(
+                specifics
+                    .requested_team
+                    .as_ref()
+                    .map(|team| team.name.as_str()),
+            );
+
+            if reviewer_names.is_empty() {
+                return Err(RockdoveError::MissingField {
+                    event_type: event_type.clone(),
+                    field: "(requested_reviewer|requested_team)",
+                });
+            }
+
+            format!("review requested from {}", reviewer_names.join(", "))
+        }
+        _ => {
+            return Ok(None);
+        }
+    }))
+}
+
+/// Describes the commits pushed since we last posted, unless that was too
+/// recent. A replay was already counted when it first came in, so it isn't
+/// held back and doesn't know how many commits are new.
+fn describe_push(
+    state: &State,
+    key: &str,
+    commits: u64,
+    settings: &Settings,
+) -> RockdoveResult<Option<String>> {
+    if !settings.synchronize {
+        return Ok(None);
+    }
+    let last: Option<Pushed> = if state.replay() {
+        None
+    } else {
+        state
+            .get(key)?
+            .and_then(|value| serde_json::from_str(&value).ok())
+    };
+    if last
+        .as_ref()
+        .is_some_and(|last| store::now() - last.posted_at < settings.synchronize_interval_secs)
+    {
+        return Ok(None);
+    }
+    remember_commits(state, key, commits)?;
+
+    Ok(Some(
+        match last.map(|last| commits.saturating_sub(last.commits)) {
+            Some(1) => "updated with 1 new commit".to_string(),
+            Some(0) => "updated".to_string(),
+            Some(count) => format!("updated with {count} new commits"),
+            // opened before we started keeping count.
+            None => "updated with new commits".to_string(),
+        },
+    ))
+}
+
+fn remember_commits(state: &State, key: &str, commits: u64) -> RockdoveResult<()> {
+    let pushed = Pushed {
+        posted_at: store::now(),
+        commits,
+    };
+    state.set(
+        key,
+        &serde_json::to_string(&pushed).map_err(store::Error::from)?,
+    );
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::snapshot_test;
+    use crate::{events::Settings, snapshot_test, store::Store, tests::render};
     use yare::parameterized;
 
     #[parameterized(
@@ -128,6 +304,10 @@ mod tests {
         closed = { "closed" },
         reopened = { "reopened" },
         multiple_reviewers = { "multiple_reviewers" },
+        labeled = { "labeled" },
+        milestoned = { "milestoned" },
+        unassigned = { "unassigned" },
+        converted_to_draft = { "converted_to_draft" },
     )]
     fn snapshot(event_type: &str) {
         snapshot_test!("pull_request", event_type);
@@ -135,14 +315,54 @@ mod tests {
 
     #[test]
     fn test_userstyles_is_banned() {
-        let embed = crate::events::make_embed(
-            octocrab::models::webhook_events::WebhookEvent::try_from_header_and_body(
-                "pull_request",
-                include_str!("../../fixtures/pull_request/userstyles_review.json"),
-            )
-            .expect("event fixture is valid"),
-        )
-        .expect("make_embed should succeed");
+        let store = crate::store::Store::open_in_memory().expect("in-memory store opens");
+        let embed = crate::tests::render(
+            "pull_request",
+            "userstyles_review",
+            &crate::events::Settings::default(),
+            &store,
+        );
         assert_eq!(embed, None, "userstyles should be ignored");
     }
+
+    #[test]
+    fn test_drafts_are_skipped_until_ready() {
+        let mut settings = Settings::default();
+        settings.pull_request.skip_drafts = true;
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        assert_eq!(
+            render("pull_request", "opened_draft", &settings, &store),
+            None,
+            "drafts should be skipped"
+        );
+        assert_eq!(
+            render("pull_request", "labeled_draft", &settings, &store),
+            None,
+            "nothing about a draft is posted until it's ready"
+        );
+        snapshot_test!("pull_request", "ready_for_review", &settings, &store);
+    }
+
+    #[test]
+    fn test_synchronize_is_throttled() {
+        let mut settings = Settings::default();
+        settings.pull_request.synchronize = true;
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        store
+            .set_state(
+                "pull_request_commits:catppuccin-rfc/polybar#14",
+                r#"{"posted_at":0,"commits":3}"#,
+            )
+            .unwrap();
+        snapshot_test!("pull_request", "synchronize", &settings, &store);
+        assert!(
+            render("pull_request", "synchronize", &settings, &store).is_some(),
+            "a push that was never queued isn't counted"
+        );
+        assert_eq!(
+            render("pull_request", "synchronize", &settings, &store),
+            None,
+            "a second push straight after should be held back"
+        );
+    }
 }
@@ -4,24 +4,27 @@ use octocrab::models::webhook_events::{
         PullRequestReviewCommentWebhookEventAction, PullRequestReviewCommentWebhookEventPayload,
     },
 };
+use serde::Deserialize;
 
 use crate::{
-    colors::PULL_REQUEST_COLOR,
+    colors::{COMMENT_DELETED_COLOR, PULL_REQUEST_COLOR},
     embed_builder::EmbedBuilder,
     errors::{RockdoveError, RockdoveResult},
+    events::issue_comment::{describe_deleted, describe_edit},
 };
 
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Post a diff when a review comment is edited.
+    pub edits: bool,
+}
+
 pub fn make_embed(
     event: WebhookEvent,
     specifics: &PullRequestReviewCommentWebhookEventPayload,
+    settings: &Settings,
 ) -> RockdoveResult<Option<EmbedBuilder>> {
-    if !matches!(
-        specifics.action,
-        PullRequestReviewCommentWebhookEventAction::Created
-    ) {
-        return Ok(None);
-    }
-
     let repo = event
         .repository
         .ok_or_else(|| RockdoveError::MissingField {
@@ -32,10 +35,8 @@ pub fn make_embed(
     let mut embed = EmbedBuilder::default();
 
     let repo_name = repo.full_name.unwrap_or(repo.name);
-
-    embed.title(&format!(
-        "[{}] New review comment on pull request #{}: {}",
-        repo_name,
+    let on = format!(
+        "pull request #{}: {}",
         specifics.pull_request.number,
         specifics
             .pull_request
@@ -45,20 +46,77 @@ pub fn make_embed(
                 event_type: event.kind.clone(),
                 field: "pull_request.title",
             })?,
-    ));
+    );
+    let body = specifics.comment.body.as_str();
+
+    match specifics.action {
+        PullRequestReviewCommentWebhookEventAction::Created => {
+            embed.title(&format!("[{repo_name}] New review comment on {on}"));
+            embed.description(body);
+        }
+        PullRequestReviewCommentWebhookEventAction::Edited => {
+            if !settings.edits {
+                return Ok(None);
+            }
+            let before = specifics
+                .changes
+                .as_ref()
+                .and_then(|changes| changes.body.as_ref())
+                .ok_or_else(|| RockdoveError::MissingField {
+                    event_type: event.kind.clone(),
+                    field: "changes.body",
+                })?;
+            let Some(diff) = describe_edit(&before.from, body) else {
+                return Ok(None);
+            };
+            embed.title(&format!("[{repo_name}] Review comment edited on {on}"));
+            embed.description(&diff);
+        }
+        PullRequestReviewCommentWebhookEventAction::Deleted => {
+            embed.title(&format!("[{repo_name}] Review comment deleted on {on}"));
+            embed.description(&describe_deleted(
+                specifics
+                    .comment
+                    .user
+                    .as_ref()
+                    .map_or("ghost", |user| user.login.as_str()),
+                specifics.comment.created_at,
+                body,
+            ));
+        }
+        _ => return Ok(None),
+    }
+
     embed.url(specifics.comment.html_url.as_str());
-    embed.description(specifics.comment.body.as_str());
-    embed.color(PULL_REQUEST_COLOR);
+    embed.color(
+        if specifics.action == PullRequestReviewCommentWebhookEventAction::Deleted {
+            COMMENT_DELETED_COLOR
+        } else {
+            PULL_REQUEST_COLOR
+        },
+    );
 
     Ok(Some(embed))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::snapshot_test;
+    use crate::{events::Settings, snapshot_test};
+
+    use yare::parameterized;
+
+    #[parameterized(
+        created = { "created" },
+        deleted = { "deleted" },
+    )]
+    fn snapshot(event_type: &str) {
+        snapshot_test!("pull_request_review_comment", event_type);
+    }
 
     #[test]
-    fn created() {
-        snapshot_test!("pull_request_review_comment", "created");
+    fn test_edits() {
+        let mut settings = Settings::default();
+        settings.pull_request_review_comment.edits = true;
+        snapshot_test!("pull_request_review_comment", "edited", &settings);
     }
 }
@@ -0,0 +1,139 @@
+use octocrab::models::webhook_events::{
+    WebhookEvent,
+    payload::{PushWebhookEventCommit, PushWebhookEventPayload},
+};
+use serde::Deserialize;
+
+use crate::{
+    colors::{COMMIT_COLOR, FORCE_PUSH_COLOR},
+    embed_builder::{EmbedBuilder, MAX_DESCRIPTION_LENGTH, fit_lines},
+    errors::{RockdoveError, RockdoveResult},
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// The most commits listed in a single embed.
+    pub max_commits: usize,
+    /// Also post pushes to branches other than the default branch.
+    pub all_branches: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            max_commits: 5,
+            all_branches: false,
+        }
+    }
+}
+
+const MAX_COMMIT_LINE_LENGTH: usize = 72;
+
+pub fn make_embed(
+    event: WebhookEvent,
+    specifics: &PushWebhookEventPayload,
+    settings: &Settings,
+) -> RockdoveResult<Option<EmbedBuilder>> {
+    // tags and branch deletions are handled by the `create` and `delete` events.
+    let Some(branch) = specifics.r#ref.strip_prefix("refs/heads/") else {
+        return Ok(None);
+    };
+    if specifics.deleted || (specifics.commits.is_empty() && !specifics.forced) {
+        return Ok(None);
+    }
+
+    let repo = event
+        .repository
+        .ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "repository",
+        })?;
+
+    if !settings.all_branches && repo.default_branch.as_deref() != Some(branch) {
+        return Ok(None);
+    }
+
+    let mut embed = EmbedBuilder::default();
+
+    let repo_name = repo.full_name.unwrap_or(repo.name);
+    let count = specifics.commits.len();
+    let commits = if count == 1 { "commit" } else { "commits" };
+
+    embed.title(&if specifics.forced {
+        format!("[{repo_name}:{branch}] Force-pushed {count} {commits}")
+    } else {
+        format!("[{repo_name}:{branch}] {count} new {commits}")
+    });
+
+    embed.url(specifics.compare.as_str());
+
+    if count > 0 {
+        let lines: Vec<_> = specifics.commits.iter().map(commit_line).collect();
+        embed.description(&fit_lines(
+            &lines,
+            settings.max_commits,
+            MAX_DESCRIPTION_LENGTH,
+        ));
+    }
+
+    embed.color(if specifics.forced {
+        FORCE_PUSH_COLOR
+    } else {
+        COMMIT_COLOR
+    });
+
+    Ok(Some(embed))
+}
+
+fn commit_line(commit: &PushWebhookEventCommit) -> String {
+    let summary = commit.message.lines().next().unwrap_or_default();
+    let summary = if summary.chars().count() > MAX_COMMIT_LINE_LENGTH {
+        let truncated: String = summary.chars().take(MAX_COMMIT_LINE_LENGTH - 3).collect();
+        format!("{truncated}...")
+    } else {
+        summary.to_string()
+    };
+    format!(
+        "[`{}`]({}) {} - {}",
+        &commit.id[..7.min(commit.id.len())],
+        commit.url,
+        summary,
+        commit.author.user.name,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{events::Settings, snapshot_test};
+
+    use yare::parameterized;
+
+    #[parameterized(
+        pushed = { "pushed" },
+        force_pushed = { "force_pushed" },
+        many_commits = { "many_commits" },
+    )]
+    fn snapshot(event_type: &str) {
+        snapshot_test!("push", event_type);
+    }
+
+    #[test]
+    fn test_non_default_branch_is_ignored() {
+        let store = crate::store::Store::open_in_memory().expect("in-memory store opens");
+        let embed = crate::tests::render(
+            "push",
+            "non_default_branch",
+            &crate::events::Settings::default(),
+            &store,
+        );
+        assert_eq!(embed, None, "non-default branches should be ignored");
+    }
+
+    #[test]
+    fn test_all_branches() {
+        let mut settings = Settings::default();
+        settings.push.all_branches = true;
+        snapshot_test!("push", "non_default_branch", &settings);
+    }
+}
@@ -2,20 +2,66 @@ use octocrab::models::webhook_events::{
     WebhookEvent,
     payload::{ReleaseWebhookEventAction, ReleaseWebhookEventPayload},
 };
+use serde::Deserialize;
 
 use crate::{
-    colors::RELEASE_COLOR,
-    embed_builder::EmbedBuilder,
+    colors::{PRERELEASE_COLOR, RELEASE_COLOR},
+    embed_builder::{EmbedBuilder, MAX_DESCRIPTION_LENGTH, fit_lines, limit_text_length},
     errors::{RockdoveError, RockdoveResult},
+    store::Tracking,
 };
 
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Update the announcement when a published release is edited.
+    pub edits: bool,
+}
+
+/// The most assets listed in a single embed.
+const MAX_ASSETS: usize = 5;
+/// The room kept for the release notes when there are lots of assets.
+const MIN_NOTES_LENGTH: usize = 200;
+
+#[derive(Deserialize)]
+struct Release {
+    id: u64,
+    name: Option<String>,
+    tag_name: String,
+    html_url: String,
+    body: Option<String>,
+    draft: bool,
+    prerelease: bool,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    size: u64,
+    browser_download_url: String,
+}
+
 pub fn make_embed(
     event: WebhookEvent,
     specifics: &ReleaseWebhookEventPayload,
+    settings: &Settings,
 ) -> RockdoveResult<Option<EmbedBuilder>> {
-    if !matches!(specifics.action, ReleaseWebhookEventAction::Released) {
-        return Ok(None);
-    }
+    let release =
+        Release::deserialize(&specifics.release).map_err(|_| RockdoveError::InvalidField {
+            event_type: event.kind.clone(),
+            field: "release",
+        })?;
+
+    let tracking = match specifics.action {
+        ReleaseWebhookEventAction::Released | ReleaseWebhookEventAction::Prereleased => {
+            Tracking::Remember(format!("release:{}", release.id))
+        }
+        ReleaseWebhookEventAction::Edited if settings.edits && !release.draft => {
+            Tracking::Edit(format!("release:{}", release.id))
+        }
+        _ => return Ok(None),
+    };
 
     let repo = event
         .repository
@@ -27,43 +73,149 @@ pub fn make_embed(
     let mut embed = EmbedBuilder::default();
 
     let repo_name = repo.full_name.unwrap_or(repo.name);
+    let name = release
+        .name
+        .as_deref()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(&release.tag_name);
+
+    embed.title(&if release.prerelease {
+        format!("[{repo_name}] New prerelease published: {name}")
+    } else {
+        format!("[{repo_name}] New release published: {name}")
+    });
+
+    embed.url(&release.html_url);
 
-    embed.title(&format!(
-        "[{}] New release published: {}",
-        repo_name,
-        specifics
-            .release
-            .get("name")
-            .and_then(|v| v.as_str())
-            .unwrap_or("*no name*"),
-    ));
-
-    embed.url(
-        specifics
-            .release
-            .get("html_url")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| RockdoveError::MissingField {
-                event_type: event.kind.clone(),
-                field: "release.html_url",
-            })?,
-    );
-
-    if let Some(body) = specifics.release.get("body").and_then(|v| v.as_str()) {
-        embed.description(body);
+    let description = describe(&release);
+    if !description.is_empty() {
+        embed.description(&description);
     }
 
-    embed.color(RELEASE_COLOR);
+    embed.color(if release.prerelease {
+        PRERELEASE_COLOR
+    } else {
+        RELEASE_COLOR
+    });
+
+    embed.tracking(tracking);
 
     Ok(Some(embed))
 }
 
+/// The prerelease badge, release notes and assets. The notes are shortened
+/// to make room for the assets, which are more useful than the end of the
+/// notes.
+fn describe(release: &Release) -> String {
+    let badge = if release.prerelease {
+        "**Prerelease**"
+    } else {
+        ""
+    };
+    let assets = if release.assets.is_empty() {
+        String::new()
+    } else {
+        let lines: Vec<_> = release.assets.iter().map(asset_line).collect();
+        format!(
+            "**Assets:**\n{}",
+            fit_lines(
+                &lines,
+                MAX_ASSETS,
+                MAX_DESCRIPTION_LENGTH - MIN_NOTES_LENGTH
+            )
+        )
+    };
+
+    // two blank lines between each of the three parts.
+    let room = MAX_DESCRIPTION_LENGTH - badge.len() - assets.len() - 4;
+    let body = limit_text_length(release.body.as_deref().unwrap_or_default(), room);
+
+    [badge, &body, &assets]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn asset_line(asset: &Asset) -> String {
+    format!(
+        "[{}]({}) ({})",
+        asset.name,
+        asset.browser_download_url,
+        format_size(asset.size)
+    )
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    // each unit is 1024 of the one before, and anything bigger stays in TB.
+    let power = (bytes.ilog2() / 10).min(4);
+    // one decimal place, without going through floats.
+    let tenths = (u128::from(bytes) * 10) >> (power * 10);
+    format!(
+        "{}.{} {}",
+        tenths / 10,
+        tenths % 10,
+        UNITS[power as usize - 1]
+    )
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::snapshot_test;
+    use crate::{events::Settings, snapshot_test, store::Tracking};
+
+    use yare::parameterized;
+
+    use super::format_size;
+
+    #[parameterized(
+        released = { "released" },
+        prereleased = { "prereleased" },
+        with_assets = { "with_assets" },
+      )]
+    fn snapshot(event_type: &str) {
+        snapshot_test!("release", event_type);
+    }
+
+    #[test]
+    fn test_edits() {
+        let mut settings = Settings::default();
+        settings.release.edits = true;
+        snapshot_test!("release", "edited", &settings);
+    }
+
+    #[test]
+    fn test_edits_update_the_announcement() {
+        let mut settings = Settings::default();
+        let store = crate::store::Store::open_in_memory().expect("in-memory store opens");
+        let render = |settings: &Settings, fixture: &str| {
+            crate::tests::render_message("release", fixture, settings, &store)
+                .map(|msg| msg.tracking)
+        };
+
+        let key = "release:176255524".to_string();
+        assert_eq!(
+            render(&settings, "released"),
+            Some(Some(Tracking::Remember(key.clone())))
+        );
+        assert_eq!(
+            render(&settings, "edited"),
+            None,
+            "edits are off by default"
+        );
+        settings.release.edits = true;
+        assert_eq!(render(&settings, "edited"), Some(Some(Tracking::Edit(key))));
+    }
 
     #[test]
-    fn released() {
-        snapshot_test!("release", "released");
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MB");
+        assert_eq!(format_size(1024_u64.pow(4) - 1), "1023.9 GB");
+        assert_eq!(format_size(2048 * 1024_u64.pow(4)), "2048.0 TB");
     }
 }
@@ -85,6 +85,8 @@ pub fn make_embed(
                 )
             }
             RepositoryWebhookEventAction::Unarchived => "unarchived".to_string(),
+            RepositoryWebhookEventAction::Privatized => "made private".to_string(),
+            RepositoryWebhookEventAction::Publicized => "made public".to_string(),
             _ => {
                 return Ok(None);
             }
@@ -119,6 +121,8 @@ mod tests {
       created = { "created" },
       renamed = { "renamed" },
       transferred = { "transferred" },
+      privatized = { "privatized" },
+      publicized = { "publicized" },
     )]
     fn snapshot(event_type: &str) {
         snapshot_test!("repository", event_type);
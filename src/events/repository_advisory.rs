@@ -0,0 +1,112 @@
+use octocrab::models::webhook_events::WebhookEvent;
+use serde::Deserialize;
+
+use crate::{
+    colors::severity_color,
+    embed_builder::EmbedBuilder,
+    errors::{RockdoveError, RockdoveResult},
+};
+
+/// The parts of the payload that we use. octocrab's own payload expects
+/// fields that github doesn't send, so the event arrives untyped.
+#[derive(Deserialize)]
+struct Payload {
+    action: String,
+    repository_advisory: Advisory,
+}
+
+#[derive(Deserialize)]
+struct Advisory {
+    ghsa_id: String,
+    cve_id: Option<String>,
+    summary: String,
+    severity: Option<String>,
+    html_url: String,
+    #[serde(default)]
+    vulnerabilities: Vec<Vulnerability>,
+}
+
+#[derive(Deserialize)]
+struct Vulnerability {
+    package: Package,
+    vulnerable_version_range: Option<String>,
+    patched_versions: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    ecosystem: String,
+    name: Option<String>,
+}
+
+pub fn make_embed(
+    event: WebhookEvent,
+    specifics: &serde_json::Value,
+) -> RockdoveResult<Option<EmbedBuilder>> {
+    let payload: Payload =
+        serde_json::from_value(specifics.clone()).map_err(|_| RockdoveError::InvalidField {
+            event_type: event.kind.clone(),
+            field: "repository_advisory",
+        })?;
+
+    let verb = match payload.action.as_str() {
+        "published" => "published",
+        "reported" => "reported",
+        _ => return Ok(None),
+    };
+    let advisory = payload.repository_advisory;
+
+    let repo = event
+        .repository
+        .ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "repository",
+        })?;
+
+    let mut embed = EmbedBuilder::default();
+
+    embed.title(&format!(
+        "[{}] Security advisory {}: {}",
+        repo.full_name.unwrap_or(repo.name),
+        verb,
+        advisory.summary,
+    ));
+
+    embed.url(&advisory.html_url);
+
+    let severity = advisory.severity.as_deref().unwrap_or("unknown");
+    let mut lines = vec![format!("**Severity:** {severity}")];
+    lines.push(match &advisory.cve_id {
+        Some(cve) => format!("**Advisory:** {} ({})", advisory.ghsa_id, cve),
+        None => format!("**Advisory:** {}", advisory.ghsa_id),
+    });
+    for vulnerability in &advisory.vulnerabilities {
+        let mut parts = vec![format!(
+            "**Package:** {} ({})",
+            vulnerability.package.name.as_deref().unwrap_or("*unknown*"),
+            vulnerability.package.ecosystem,
+        )];
+        if let Some(range) = &vulnerability.vulnerable_version_range {
+            parts.push(format!("vulnerable `{range}`"));
+        }
+        if let Some(patched) = &vulnerability.patched_versions {
+            parts.push(format!("patched in `{patched}`"));
+        }
+        lines.push(parts.join(", "));
+    }
+    embed.description(&lines.join("\n"));
+
+    embed.color(severity_color(severity));
+
+    Ok(Some(embed))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snapshot_test;
+
+    #[test]
+    fn published() {
+        snapshot_test!("repository_advisory", "published");
+    }
+}
@@ -0,0 +1,134 @@
+use octocrab::models::webhook_events::{
+    WebhookEvent,
+    payload::{SecretScanningAlertWebhookEventAction, SecretScanningAlertWebhookEventPayload},
+};
+use serde::Deserialize;
+
+use crate::{
+    colors::{ALERT_RESOLVED_COLOR, SEVERITY_CRITICAL_COLOR},
+    embed_builder::EmbedBuilder,
+    errors::{RockdoveError, RockdoveResult},
+};
+
+/// Leaves out the leaked secret, so that it can never end up in an embed.
+#[derive(Deserialize)]
+struct Alert {
+    number: u64,
+    html_url: String,
+    secret_type_display_name: Option<String>,
+    secret_type: String,
+    validity: Option<String>,
+    #[serde(default)]
+    publicly_leaked: bool,
+    resolution: Option<String>,
+}
+
+/// Removes the leaked secret from the payload, which is kept with queued jobs
+/// and dead letters and shown by the admin endpoints.
+pub fn redact(payload: &mut serde_json::Value) {
+    if let Some(alert) = payload
+        .get_mut("alert")
+        .and_then(serde_json::Value::as_object_mut)
+    {
+        alert.remove("secret");
+    }
+}
+
+pub fn make_embed(
+    event: WebhookEvent,
+    specifics: &SecretScanningAlertWebhookEventPayload,
+) -> RockdoveResult<Option<EmbedBuilder>> {
+    let (verb, resolved) = match specifics.action {
+        SecretScanningAlertWebhookEventAction::Created => ("opened", false),
+        SecretScanningAlertWebhookEventAction::Reopened => ("reopened", false),
+        SecretScanningAlertWebhookEventAction::Resolved => ("resolved", true),
+        _ => return Ok(None),
+    };
+
+    let alert: Alert = serde_json::from_value(specifics.alert.clone()).map_err(|_| {
+        RockdoveError::InvalidField {
+            event_type: event.kind.clone(),
+            field: "alert",
+        }
+    })?;
+
+    let repo = event
+        .repository
+        .ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "repository",
+        })?;
+
+    let mut embed = EmbedBuilder::default();
+
+    embed.title(&format!(
+        "[{}] Secret scanning alert #{} {}: {}",
+        repo.full_name.unwrap_or(repo.name),
+        alert.number,
+        verb,
+        alert
+            .secret_type_display_name
+            .as_deref()
+            .unwrap_or(&alert.secret_type),
+    ));
+
+    embed.url(&alert.html_url);
+
+    let mut lines = vec![format!("**Secret type:** `{}`", alert.secret_type)];
+    if let Some(validity) = &alert.validity {
+        lines.push(format!("**Validity:** {validity}"));
+    }
+    if alert.publicly_leaked {
+        lines.push("**Publicly leaked**".to_string());
+    }
+    if let Some(resolution) = &alert.resolution {
+        lines.push(format!("**Resolution:** {}", resolution.replace('_', " ")));
+    }
+    embed.description(&lines.join("\n"));
+
+    // a leaked secret is always worth treating as critical.
+    embed.color(if resolved {
+        ALERT_RESOLVED_COLOR
+    } else {
+        SEVERITY_CRITICAL_COLOR
+    });
+
+    Ok(Some(embed))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{events::Settings, snapshot_test, store::Store, tests::render};
+
+    #[test]
+    fn created() {
+        snapshot_test!("secret_scanning_alert", "created");
+    }
+
+    #[test]
+    fn test_secret_is_never_rendered() {
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        let embed = render(
+            "secret_scanning_alert",
+            "created",
+            &Settings::default(),
+            &store,
+        )
+        .expect("alert is rendered");
+        assert!(
+            !embed.to_string().contains("ghp_"),
+            "the secret must never be included"
+        );
+    }
+
+    #[test]
+    fn test_secret_is_never_stored() {
+        let mut payload: serde_json::Value = serde_json::from_str(include_str!(
+            "../../fixtures/secret_scanning_alert/created.json"
+        ))
+        .expect("fixture is valid json");
+        super::redact(&mut payload);
+        assert!(payload["alert"].get("secret").is_none());
+        assert_eq!(payload["alert"]["number"], 2);
+    }
+}
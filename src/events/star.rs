@@ -0,0 +1,214 @@
+use octocrab::models::webhook_events::{
+    WebhookEvent,
+    payload::{StarWebhookEventAction, StarWebhookEventPayload},
+};
+use serde::Deserialize;
+
+use crate::{
+    colors::STAR_COLOR,
+    embed_builder::EmbedBuilder,
+    errors::{RockdoveError, RockdoveResult},
+    events::State,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    /// Post every single event.
+    Every,
+    /// Only post when the count reaches one of the milestones.
+    Milestones,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Whether to post every event or only milestones.
+    pub mode: Mode,
+    /// The counts that are worth celebrating.
+    pub milestones: Vec<u32>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            mode: Mode::Milestones,
+            milestones: vec![100, 500, 1000, 2500, 5000, 10_000],
+        }
+    }
+}
+
+/// GitHub sends both a `star` and a `watch` event for every star, so `watch`
+/// events are only used for milestones, where counting twice doesn't matter.
+pub enum Source<'a> {
+    Star(&'a StarWebhookEventPayload),
+    Watch,
+}
+
+pub fn make_embed(
+    event: WebhookEvent,
+    source: &Source,
+    settings: &Settings,
+    state: &State,
+) -> RockdoveResult<Option<EmbedBuilder>> {
+    match (source, settings.mode) {
+        (Source::Star(specifics), _)
+            if !matches!(specifics.action, StarWebhookEventAction::Created) =>
+        {
+            return Ok(None);
+        }
+        (Source::Watch, Mode::Every) => return Ok(None),
+        _ => {}
+    }
+
+    let repo = event
+        .repository
+        .ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "repository",
+        })?;
+
+    let stars = repo
+        .stargazers_count
+        .ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "repository.stargazers_count",
+        })?;
+
+    let mut embed = EmbedBuilder::default();
+
+    let repo_name = repo.full_name.unwrap_or(repo.name);
+
+    match settings.mode {
+        Mode::Every => {
+            embed.title(&format!("[{repo_name}] New star"));
+            embed.description(&format!("Now at {stars} stars"));
+        }
+        Mode::Milestones => {
+            let Some(milestone) = crossed_milestone(
+                state,
+                &format!("star_milestone:{repo_name}"),
+                stars,
+                &settings.milestones,
+            )?
+            else {
+                return Ok(None);
+            };
+            embed.title(&format!("[{repo_name}] Reached {milestone} stars"));
+        }
+    }
+
+    embed.url(&format!(
+        "{}/stargazers",
+        repo.html_url.ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "repository.html_url",
+        })?
+    ));
+
+    embed.color(STAR_COLOR);
+
+    Ok(Some(embed))
+}
+
+/// The highest milestone passed since the last event, if any. The first event
+/// for a repository only records the count, since milestones it passed before
+/// rockdove was watching are old news. Only the highest count seen is kept, so
+/// losing and regaining a star around a milestone doesn't post it again. A
+/// replay posts the highest milestone reached, since its count was already
+/// recorded when it first came in.
+pub fn crossed_milestone(
+    state: &State,
+    key: &str,
+    count: u32,
+    milestones: &[u32],
+) -> RockdoveResult<Option<u32>> {
+    let previous = state
+        .get(key)?
+        .and_then(|previous| previous.parse::<u32>().ok());
+    if previous.is_none_or(|previous| count > previous) {
+        state.set(key, &count.to_string());
+    }
+    let Some(previous) = previous else {
+        return Ok(None);
+    };
+    Ok(milestones
+        .iter()
+        .copied()
+        .filter(|m| (state.replay() || previous < *m) && *m <= count)
+        .max())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        events::{Settings, star::Mode},
+        snapshot_test,
+        store::Store,
+        tests::render,
+    };
+
+    #[test]
+    fn milestone() {
+        let settings = Settings::default();
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        render("star", "created", &settings, &store);
+        snapshot_test!("star", "milestone", &settings, &store);
+    }
+
+    #[test]
+    fn created() {
+        let mut settings = Settings::default();
+        settings.star.mode = Mode::Every;
+        snapshot_test!("star", "created", &settings);
+    }
+
+    #[test]
+    fn test_milestones_are_only_posted_once() {
+        let settings = Settings::default();
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        assert_eq!(
+            render("star", "created", &settings, &store),
+            None,
+            "stars between milestones aren't posted"
+        );
+        assert!(render("star", "milestone", &settings, &store).is_some());
+        assert_eq!(render("star", "milestone", &settings, &store), None);
+        assert_eq!(
+            render("watch", "started", &settings, &store),
+            None,
+            "the matching watch event doesn't post the milestone again"
+        );
+    }
+
+    #[test]
+    fn test_first_sighting_only_records_the_count() {
+        let settings = Settings::default();
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        assert_eq!(
+            render("star", "popular", &settings, &store),
+            None,
+            "milestones passed before rockdove was watching aren't posted"
+        );
+        assert_eq!(
+            store
+                .state("star_milestone:catppuccin-rfc/polybar")
+                .unwrap(),
+            Some("10234".to_string())
+        );
+        assert_eq!(
+            render("star", "milestone", &settings, &store),
+            None,
+            "falling back below a milestone doesn't post it again"
+        );
+        assert_eq!(render("star", "popular", &settings, &store), None);
+    }
+
+    #[test]
+    fn test_watch_is_ignored_for_every_star() {
+        let mut settings = Settings::default();
+        settings.star.mode = Mode::Every;
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        assert_eq!(render("watch", "started", &settings, &store), None);
+    }
+}
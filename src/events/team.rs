@@ -0,0 +1,136 @@
+use octocrab::models::webhook_events::{
+    WebhookEvent,
+    payload::{TeamWebhookEventAction, TeamWebhookEventPayload},
+};
+use serde::Deserialize;
+
+use crate::{
+    colors::MEMBERSHIP_COLOR,
+    embed_builder::EmbedBuilder,
+    errors::{RockdoveError, RockdoveResult},
+};
+
+#[derive(Deserialize)]
+struct Team {
+    name: String,
+    html_url: String,
+    permission: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Changes {
+    name: Option<Changed>,
+    description: Option<Changed>,
+    privacy: Option<Changed>,
+}
+
+#[derive(Deserialize)]
+struct Changed {
+    from: String,
+}
+
+pub fn make_embed(
+    event: WebhookEvent,
+    specifics: &TeamWebhookEventPayload,
+) -> RockdoveResult<Option<EmbedBuilder>> {
+    let team = Team::deserialize(&specifics.team).map_err(|_| RockdoveError::InvalidField {
+        event_type: event.kind.clone(),
+        field: "team",
+    })?;
+
+    let org = event
+        .organization
+        .ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "organization",
+        })?;
+
+    let mut embed = EmbedBuilder::default();
+
+    embed.title(&format!(
+        "[{}] Team {} {}",
+        org.login,
+        team.name,
+        match specifics.action {
+            TeamWebhookEventAction::Created => "created".to_string(),
+            TeamWebhookEventAction::Deleted => "deleted".to_string(),
+            TeamWebhookEventAction::Edited => "edited".to_string(),
+            TeamWebhookEventAction::AddedToRepository
+            | TeamWebhookEventAction::RemovedFromRepository => {
+                let repo = event
+                    .repository
+                    .ok_or_else(|| RockdoveError::MissingField {
+                        event_type: event.kind.clone(),
+                        field: "repository",
+                    })?;
+                format!(
+                    "{} {}",
+                    if matches!(specifics.action, TeamWebhookEventAction::AddedToRepository) {
+                        "added to"
+                    } else {
+                        "removed from"
+                    },
+                    repo.full_name.unwrap_or(repo.name),
+                )
+            }
+            _ => {
+                return Ok(None);
+            }
+        },
+    ));
+
+    embed.url(&team.html_url);
+
+    match specifics.action {
+        TeamWebhookEventAction::Edited => {
+            let changes = specifics
+                .changes
+                .as_ref()
+                .map(Changes::deserialize)
+                .transpose()
+                .map_err(|_| RockdoveError::InvalidField {
+                    event_type: event.kind.clone(),
+                    field: "changes",
+                })?
+                .unwrap_or_default();
+            let lines: Vec<String> = [
+                ("Renamed from", changes.name),
+                ("Description was", changes.description),
+                ("Privacy was", changes.privacy),
+            ]
+            .into_iter()
+            .filter_map(|(what, change)| Some(format!("{what} {}", change?.from)))
+            .collect();
+            if !lines.is_empty() {
+                embed.description(&lines.join("\n"));
+            }
+        }
+        TeamWebhookEventAction::AddedToRepository => {
+            if let Some(permission) = &team.permission {
+                embed.description(&format!("**Permission:** {permission}"));
+            }
+        }
+        _ => {}
+    }
+
+    embed.color(MEMBERSHIP_COLOR);
+
+    Ok(Some(embed))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snapshot_test;
+
+    use yare::parameterized;
+
+    #[parameterized(
+        created = { "created" },
+        edited = { "edited" },
+        added_to_repository = { "added_to_repository" },
+      )]
+    fn snapshot(event_type: &str) {
+        snapshot_test!("team", event_type);
+    }
+}
@@ -0,0 +1,286 @@
+use octocrab::models::webhook_events::{
+    WebhookEvent,
+    payload::{WorkflowRunWebhookEventAction, WorkflowRunWebhookEventPayload},
+};
+use serde::Deserialize;
+
+use crate::{
+    colors::{CI_FAILED_COLOR, CI_FIXED_COLOR},
+    embed_builder::EmbedBuilder,
+    errors::{RockdoveError, RockdoveResult},
+    events::State,
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// The conclusions that count as a failure worth posting.
+    pub conclusions: Vec<String>,
+    /// Post when a run succeeds after a failure.
+    pub notify_fixed: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            conclusions: default_conclusions(),
+            notify_fixed: true,
+        }
+    }
+}
+
+pub fn default_conclusions() -> Vec<String> {
+    ["failure", "timed_out", "cancelled"]
+        .map(ToString::to_string)
+        .to_vec()
+}
+
+#[derive(Deserialize)]
+struct WorkflowRun {
+    name: String,
+    head_branch: Option<String>,
+    head_sha: String,
+    conclusion: Option<String>,
+    html_url: String,
+    workflow_id: u64,
+    head_commit: Option<HeadCommit>,
+    event: String,
+    head_repository: Option<HeadRepository>,
+}
+
+#[derive(Deserialize)]
+struct HeadRepository {
+    full_name: String,
+}
+
+#[derive(Deserialize)]
+pub struct HeadCommit {
+    message: String,
+}
+
+pub enum Outcome {
+    Failed(String),
+    Fixed,
+}
+
+pub fn make_embed(
+    event: WebhookEvent,
+    specifics: &WorkflowRunWebhookEventPayload,
+    settings: &Settings,
+    state: &State,
+) -> RockdoveResult<Option<EmbedBuilder>> {
+    if !matches!(specifics.action, WorkflowRunWebhookEventAction::Completed) {
+        return Ok(None);
+    }
+
+    let run: WorkflowRun =
+        serde_json::from_value(specifics.workflow_run.clone()).map_err(|_| {
+            RockdoveError::InvalidField {
+                event_type: event.kind.clone(),
+                field: "workflow_run",
+            }
+        })?;
+
+    let repo = event
+        .repository
+        .ok_or_else(|| RockdoveError::MissingField {
+            event_type: event.kind.clone(),
+            field: "repository",
+        })?;
+
+    let repo_name = repo.full_name.unwrap_or(repo.name);
+
+    // only runs on the default branch matter, pull requests are work in
+    // progress. a pull request from a fork's default branch has the same
+    // branch name, so it's told apart by where the commit came from.
+    if matches!(run.event.as_str(), "pull_request" | "pull_request_target")
+        || run.head_repository.map(|head| head.full_name).as_ref() != Some(&repo_name)
+    {
+        return Ok(None);
+    }
+    let Some(branch) = run
+        .head_branch
+        .as_deref()
+        .filter(|branch| repo.default_branch.as_deref() == Some(*branch))
+    else {
+        return Ok(None);
+    };
+    let Some(conclusion) = run.conclusion.as_deref() else {
+        return Ok(None);
+    };
+
+    let Some(outcome) = track(
+        state,
+        &format!("workflow_run:{repo_name}:{}", run.workflow_id),
+        conclusion,
+        &settings.conclusions,
+        settings.notify_fixed,
+    )?
+    else {
+        return Ok(None);
+    };
+
+    let mut embed = EmbedBuilder::default();
+
+    embed.title(&format!(
+        "[{}] Workflow {} {} on {}",
+        repo_name,
+        run.name,
+        match outcome {
+            Outcome::Failed(ref verb) => verb,
+            Outcome::Fixed => "fixed",
+        },
+        branch,
+    ));
+
+    embed.url(&run.html_url);
+
+    let html_url = repo.html_url.ok_or_else(|| RockdoveError::MissingField {
+        event_type: event.kind.clone(),
+        field: "repository.html_url",
+    })?;
+    embed.description(&describe(
+        html_url.as_str(),
+        &run.head_sha,
+        run.head_commit.as_ref(),
+        conclusion,
+    ));
+
+    embed.color(match outcome {
+        Outcome::Failed(_) => CI_FAILED_COLOR,
+        Outcome::Fixed => CI_FIXED_COLOR,
+    });
+
+    Ok(Some(embed))
+}
+
+/// Remembers the latest conclusion under `key` and decides whether it's worth
+/// posting: every failure is, and a success is only if the previous
+/// conclusion was a failure. A replayed success was posted as a fix, so it
+/// still is.
+pub fn track(
+    state: &State,
+    key: &str,
+    conclusion: &str,
+    failures: &[String],
+    notify_fixed: bool,
+) -> RockdoveResult<Option<Outcome>> {
+    if failures.iter().any(|failure| failure == conclusion) {
+        state.set(key, "failing");
+        return Ok(Some(Outcome::Failed(match conclusion {
+            "failure" => "failed".to_string(),
+            "cancelled" => "was cancelled".to_string(),
+            _ => conclusion.replace('_', " "),
+        })));
+    }
+
+    if conclusion != "success" {
+        return Ok(None);
+    }
+
+    let was_failing = state.replay() || state.get(key)?.as_deref() == Some("failing");
+    state.set(key, "passing");
+    Ok((was_failing && notify_fixed).then_some(Outcome::Fixed))
+}
+
+pub fn describe(
+    repo_url: &str,
+    sha: &str,
+    head_commit: Option<&HeadCommit>,
+    conclusion: &str,
+) -> String {
+    format!(
+        "Commit [`{}`]({}/commit/{}) {}\nConclusion: `{}`",
+        &sha[..7.min(sha.len())],
+        repo_url,
+        sha,
+        head_commit
+            .and_then(|commit| commit.message.lines().next())
+            .unwrap_or_default(),
+        conclusion,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{events::Settings, snapshot_test, store::Store, tests::render};
+
+    use yare::parameterized;
+
+    #[parameterized(
+        failure = { "failure" },
+        timed_out = { "timed_out" },
+    )]
+    fn snapshot(event_type: &str) {
+        snapshot_test!("workflow_run", event_type);
+    }
+
+    #[test]
+    fn fixed() {
+        let settings = Settings::default();
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        render("workflow_run", "failure", &settings, &store);
+        snapshot_test!("workflow_run", "success", &settings, &store);
+    }
+
+    #[test]
+    fn test_success_is_only_posted_after_a_failure() {
+        let settings = Settings::default();
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        assert_eq!(
+            render("workflow_run", "success", &settings, &store),
+            None,
+            "a passing workflow isn't news"
+        );
+        assert!(render("workflow_run", "failure", &settings, &store).is_some());
+        assert!(render("workflow_run", "success", &settings, &store).is_some());
+        assert_eq!(
+            render("workflow_run", "success", &settings, &store),
+            None,
+            "only the first success after a failure is posted"
+        );
+    }
+
+    #[test]
+    fn test_pull_requests_from_forks_are_ignored() {
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        let settings = Settings::default();
+        assert_eq!(
+            render("workflow_run", "failure_from_fork", &settings, &store),
+            None,
+            "a fork's default branch isn't ours"
+        );
+        assert_eq!(
+            store
+                .state("workflow_run:catppuccin-rfc/polybar:57174567")
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            render("workflow_run", "success", &settings, &store),
+            None,
+            "a fork failing doesn't make the next success a fix"
+        );
+    }
+
+    #[test]
+    fn test_other_branches_are_ignored() {
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        let embed = render(
+            "workflow_run",
+            "failure_on_branch",
+            &Settings::default(),
+            &store,
+        );
+        assert_eq!(embed, None, "only the default branch is watched");
+    }
+
+    #[test]
+    fn test_scheduled_runs_are_posted() {
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        assert!(
+            render("workflow_run", "scheduled", &Settings::default(), &store).is_some(),
+            "runs that aren't from a push still run on the default branch"
+        );
+    }
+}
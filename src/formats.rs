@@ -0,0 +1,128 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::store::Source;
+
+mod generic;
+mod markdown;
+mod matrix;
+mod mattermost;
+mod slack;
+mod teams;
+mod zulip;
+
+pub use generic::signature;
+
+/// What a destination expects to be sent. Messages are rendered and queued in
+/// discord's shape, then converted just before they're delivered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    #[default]
+    Discord,
+    /// A slack incoming webhook.
+    Slack,
+    /// A matrix room, posted to through the client-server api.
+    Matrix,
+    /// Any other url, sent a signed json description of the event.
+    Generic,
+    /// A microsoft teams incoming webhook or workflow, sent an adaptive card.
+    Teams,
+    /// A mattermost incoming webhook.
+    Mattermost,
+    /// A zulip stream, posted to through the messages api.
+    Zulip,
+}
+
+/// The parts of a rendered message that every format can show, read back out
+/// of its discord embed.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Embed {
+    title: String,
+    url: String,
+    description: Option<String>,
+    color: Option<u32>,
+    author: Author,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Author {
+    name: String,
+    url: Option<String>,
+    icon_url: Option<String>,
+}
+
+impl Format {
+    /// Whether messages that were already posted can be edited, which needs a
+    /// message id back from the destination.
+    pub fn can_edit(self) -> bool {
+        self == Self::Discord
+    }
+
+    /// Whether the destination needs `room` set.
+    pub const fn needs_room(self) -> bool {
+        matches!(self, Self::Matrix | Self::Zulip)
+    }
+
+    /// Whether the destination needs `token` or `token_env` set.
+    pub const fn needs_token(self) -> bool {
+        matches!(self, Self::Matrix | Self::Generic | Self::Zulip)
+    }
+
+    /// Whether the destination needs `user` set.
+    pub const fn needs_user(self) -> bool {
+        matches!(self, Self::Zulip)
+    }
+
+    /// Converts a queued message. `source` is the event it was rendered from,
+    /// for formats that describe the event as well as the message.
+    pub fn render(self, message: &Value, source: Option<&Source>) -> Value {
+        match self {
+            Self::Discord => message.clone(),
+            Self::Slack => slack::render(&Embed::from_message(message)),
+            Self::Matrix => matrix::render(&Embed::from_message(message)),
+            Self::Generic => generic::render(&Embed::from_message(message), source),
+            Self::Teams => teams::render(&Embed::from_message(message)),
+            Self::Mattermost => mattermost::render(&Embed::from_message(message)),
+            Self::Zulip => zulip::render(&Embed::from_message(message), source),
+        }
+    }
+}
+
+impl Embed {
+    fn from_message(message: &Value) -> Self {
+        Self::deserialize(&message["embeds"][0]).unwrap_or_default()
+    }
+
+    /// The colour as a `#rrggbb` hex string.
+    fn hex_color(&self) -> Option<String> {
+        self.color.map(|color| format!("#{color:06x}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use crate::colors::{RELEASE_COLOR, rgb};
+
+    /// A release announcement as it's queued, for checking what each format
+    /// makes of it.
+    pub fn release_message() -> Value {
+        json!({
+            "embeds": [{
+                "title": "[catppuccin/java] New release published: v1.0.0",
+                "url": "https://github.com/catppuccin/java/releases/tag/v1.0.0",
+                "description": "**Prerelease**\n\n~~old~~ `new`",
+                "color": rgb(RELEASE_COLOR),
+                "author": {
+                    "name": "sgoudham",
+                    "url": "https://github.com/sgoudham",
+                    "icon_url": "https://avatars.githubusercontent.com/u/58985301?v=4",
+                },
+            }],
+        })
+    }
+}
@@ -0,0 +1,148 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use super::Embed;
+use crate::store::Source;
+
+/// Bumped whenever a field is removed or changes meaning. Fields may be added
+/// without bumping it, so consumers should ignore ones they don't know.
+const SCHEMA_VERSION: u32 = 1;
+
+/// The document posted to a generic destination. Every field is present, and
+/// null when it doesn't apply.
+#[derive(Debug, Serialize)]
+struct Document<'a> {
+    version: u32,
+    /// The `X-GitHub-Delivery` id. Null for messages rockdove made up itself,
+    /// like delivery errors.
+    delivery_id: Option<&'a str>,
+    /// The `X-GitHub-Event` name, e.g. `pull_request`.
+    event: Option<&'a str>,
+    action: Option<&'a str>,
+    /// The repository's full name, e.g. `catppuccin/java`.
+    repository: Option<&'a str>,
+    actor: Option<Actor<'a>>,
+    /// The issue, pull request or discussion the event is about.
+    target: Option<Target<'a>>,
+    /// What the chat formats show.
+    title: &'a str,
+    url: &'a str,
+    description: Option<&'a str>,
+    /// `#rrggbb`.
+    color: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Actor<'a> {
+    login: &'a str,
+    url: Option<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+struct Target<'a> {
+    number: Option<u64>,
+    title: Option<&'a str>,
+    url: Option<&'a str>,
+}
+
+pub fn render(embed: &Embed, source: Option<&Source>) -> Value {
+    let payload = source.map_or(&Value::Null, |source| &source.payload);
+    let document = Document {
+        version: SCHEMA_VERSION,
+        delivery_id: source.and_then(|source| source.delivery_id.as_deref()),
+        event: source.map(|source| source.event_type.as_str()),
+        action: payload["action"].as_str(),
+        repository: payload["repository"]["full_name"].as_str(),
+        actor: payload["sender"]["login"].as_str().map(|login| Actor {
+            login,
+            url: payload["sender"]["html_url"].as_str(),
+        }),
+        target: ["pull_request", "issue", "discussion"]
+            .iter()
+            .map(|target| &payload[target])
+            .find(|target| target.is_object())
+            .map(|target| Target {
+                number: target["number"].as_u64(),
+                title: target["title"].as_str(),
+                url: target["html_url"].as_str(),
+            }),
+        title: &embed.title,
+        url: &embed.url,
+        description: embed.description.as_deref(),
+        color: embed.hex_color(),
+    };
+    serde_json::to_value(document).unwrap_or_default()
+}
+
+/// The `X-Rockdove-Signature-256` header for a body, in the same form as
+/// github's `X-Hub-Signature-256` so that the same verification code works.
+pub fn signature(body: &Value, secret: &str) -> String {
+    // reqwest serialises json bodies with `to_vec` too, so this signs exactly
+    // the bytes that are sent.
+    let bytes = serde_json::to_vec(body).unwrap_or_default();
+    format!(
+        "sha256={}",
+        hex::encode(hmac_sha256::HMAC::mac(bytes, secret))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::signature;
+    use crate::{formats::Format, store::Source};
+
+    #[test]
+    fn test_document_describes_the_event() {
+        let message = json!({
+            "embeds": [{
+                "title": "[catppuccin/java] New comment on issue #20: Reconsider OSSRH Authentication",
+                "url": "https://github.com/catppuccin/java/issues/20#issuecomment-2351090061",
+                "description": "Testing",
+                "color": 0x00a6_e3a1,
+                "author": { "name": "sgoudham" },
+            }],
+        });
+        let payload = std::fs::read_to_string(format!(
+            "{}/fixtures/issue_comment/created.json",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .expect("fixture exists");
+        let source = Source {
+            event_type: "issue_comment".to_string(),
+            delivery_id: Some("72d3162e-cc78-11e3-81ab-4c9367dc0958".to_string()),
+            payload: serde_json::from_str(&payload).expect("fixture is valid json"),
+        };
+
+        assert_eq!(
+            Format::Generic.render(&message, Some(&source)),
+            json!({
+                "version": 1,
+                "delivery_id": "72d3162e-cc78-11e3-81ab-4c9367dc0958",
+                "event": "issue_comment",
+                "action": "created",
+                "repository": "catppuccin/java",
+                "actor": { "login": "sgoudham", "url": "https://github.com/sgoudham" },
+                "target": {
+                    "number": 20,
+                    "title": "Reconsider OSSRH Authentication",
+                    "url": "https://github.com/catppuccin/java/issues/20",
+                },
+                "title": "[catppuccin/java] New comment on issue #20: Reconsider OSSRH Authentication",
+                "url": "https://github.com/catppuccin/java/issues/20#issuecomment-2351090061",
+                "description": "Testing",
+                "color": "#a6e3a1",
+            })
+        );
+    }
+
+    #[test]
+    fn test_signature_is_an_hmac_of_the_body() {
+        // from python's `hmac.new(secret, b'"Hello, World!"', hashlib.sha256)`.
+        assert_eq!(
+            signature(&json!("Hello, World!"), "It's a Secret to Everybody"),
+            "sha256=ecddb501376a06b92925a01b186582f725fd94c50d692a1033f57a5358f96a03"
+        );
+    }
+}
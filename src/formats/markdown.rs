@@ -0,0 +1,197 @@
+/// A piece of discord-flavoured markdown, for formats that spell it
+/// differently. Only the syntax that ends up in our embeds is understood;
+/// anything else is left as text.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Span {
+    Text(String),
+    Bold(Vec<Self>),
+    Italic(Vec<Self>),
+    Strike(Vec<Self>),
+    Code(String),
+    /// A fenced code block, without its language.
+    CodeBlock(String),
+    Link {
+        text: Vec<Self>,
+        url: String,
+    },
+    /// A `#` heading, which runs to the end of its line.
+    Heading(Vec<Self>),
+}
+
+pub fn parse(text: &str) -> Vec<Span> {
+    let mut spans = vec![];
+    let mut plain = String::new();
+    let mut rest = text;
+    let mut line_start = true;
+
+    while let Some(c) = rest.chars().next() {
+        let parsed = if line_start { heading(rest) } else { None }
+            .or_else(|| delimited(rest, plain.chars().last()));
+
+        if let Some((span, after)) = parsed {
+            if !plain.is_empty() {
+                spans.push(Span::Text(std::mem::take(&mut plain)));
+            }
+            spans.push(span);
+            rest = after;
+            line_start = false;
+            continue;
+        }
+
+        plain.push(c);
+        rest = &rest[c.len_utf8()..];
+        line_start = c == '\n';
+    }
+
+    if !plain.is_empty() {
+        spans.push(Span::Text(plain));
+    }
+    spans
+}
+
+/// The text of some spans with the formatting dropped, for places that can't
+/// show any.
+pub fn plain(spans: &[Span]) -> String {
+    spans
+        .iter()
+        .map(|span| match span {
+            Span::Text(text) | Span::Code(text) | Span::CodeBlock(text) => text.clone(),
+            Span::Bold(inner)
+            | Span::Italic(inner)
+            | Span::Strike(inner)
+            | Span::Heading(inner)
+            | Span::Link { text: inner, .. } => plain(inner),
+        })
+        .collect()
+}
+
+fn heading(text: &str) -> Option<(Span, &str)> {
+    let content = ["### ", "## ", "# "]
+        .iter()
+        .find_map(|prefix| text.strip_prefix(prefix))?;
+    let end = content.find('\n').unwrap_or(content.len());
+    Some((Span::Heading(parse(&content[..end])), &content[end..]))
+}
+
+/// A span opened at the start of `text`, and the text after it.
+fn delimited(text: &str, before: Option<char>) -> Option<(Span, &str)> {
+    if let Some(inner) = text.strip_prefix("```") {
+        let end = inner.find("```")?;
+        let block = &inner[..end];
+        // the first line is the language if there's anything after it.
+        let code = match block.split_once('\n') {
+            Some((language, code)) if !language.contains(' ') => code,
+            _ => block,
+        };
+        return Some((
+            Span::CodeBlock(code.trim_end_matches('\n').to_string()),
+            &inner[end + 3..],
+        ));
+    }
+    if let Some(inner) = text.strip_prefix('`') {
+        let end = inner.find('`').filter(|end| *end > 0)?;
+        return Some((Span::Code(inner[..end].to_string()), &inner[end + 1..]));
+    }
+    if let Some(inner) = text.strip_prefix("**") {
+        let end = inner.find("**").filter(|end| *end > 0)?;
+        return Some((Span::Bold(parse(&inner[..end])), &inner[end + 2..]));
+    }
+    if let Some(inner) = text.strip_prefix("~~") {
+        let end = inner.find("~~").filter(|end| *end > 0)?;
+        return Some((Span::Strike(parse(&inner[..end])), &inner[end + 2..]));
+    }
+    if let Some(inner) = text.strip_prefix('[') {
+        let close = inner.find("](")?;
+        let label = &inner[..close];
+        let after = &inner[close + 2..];
+        let end = after.find(')')?;
+        let url = &after[..end];
+        if label.contains('\n') || url.is_empty() || url.contains(char::is_whitespace) {
+            return None;
+        }
+        return Some((
+            Span::Link {
+                text: parse(label),
+                url: url.to_string(),
+            },
+            &after[end + 1..],
+        ));
+    }
+    italic(text, before)
+}
+
+/// `*text*` or `_text_`, but only at the edges of words so that `snake_case`
+/// names and multiplication are left alone.
+fn italic(text: &str, before: Option<char>) -> Option<(Span, &str)> {
+    let marker = text.chars().next().filter(|c| matches!(c, '*' | '_'))?;
+    if before.is_some_and(char::is_alphanumeric) {
+        return None;
+    }
+    let inner = &text[1..];
+    let end = inner.find(marker)?;
+    let content = &inner[..end];
+    let after = &inner[end + 1..];
+    if content.is_empty()
+        || content.starts_with(char::is_whitespace)
+        || content.ends_with(char::is_whitespace)
+        || after.starts_with(char::is_alphanumeric)
+    {
+        return None;
+    }
+    Some((Span::Italic(parse(content)), after))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Span, parse};
+
+    fn text(text: &str) -> Span {
+        Span::Text(text.to_string())
+    }
+
+    #[test]
+    fn test_inline_formatting() {
+        assert_eq!(
+            parse("**Hook:** [`abc1234`](https://example.com) ~~gone~~ *no name*"),
+            [
+                Span::Bold(vec![text("Hook:")]),
+                text(" "),
+                Span::Link {
+                    text: vec![Span::Code("abc1234".to_string())],
+                    url: "https://example.com".to_string(),
+                },
+                text(" "),
+                Span::Strike(vec![text("gone")]),
+                text(" "),
+                Span::Italic(vec![text("no name")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_code_blocks_drop_their_language() {
+        assert_eq!(
+            parse("before\n```diff\n- old\n+ new\n```"),
+            [
+                text("before\n"),
+                Span::CodeBlock("- old\n+ new".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_headings_only_start_lines() {
+        assert_eq!(
+            parse("# What's new\nissue # 4"),
+            [Span::Heading(vec![text("What's new")]), text("\nissue # 4")]
+        );
+    }
+
+    #[test]
+    fn test_unmatched_markers_are_text() {
+        assert_eq!(
+            parse("snake_case_name, 2 * 3 and [not a link]"),
+            [text("snake_case_name, 2 * 3 and [not a link]")]
+        );
+    }
+}
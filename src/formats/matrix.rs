@@ -0,0 +1,222 @@
+use serde_json::{Value, json};
+
+use super::{
+    Embed,
+    markdown::{self, Span},
+};
+
+/// An `m.room.message` event. It's a notice rather than text, which is how
+/// matrix marks messages from bots.
+pub fn render(embed: &Embed) -> Value {
+    let mut body = vec![embed.title.clone(), embed.url.clone()];
+    let mut html = vec![format!(
+        "<p><strong><a href=\"{}\">{}</a></strong></p>",
+        escape(&embed.url),
+        escape(&embed.title)
+    )];
+
+    let author = embed.author.url.as_ref().map_or_else(
+        || escape(&embed.author.name),
+        |url| {
+            format!(
+                "<a href=\"{}\">{}</a>",
+                escape(url),
+                escape(&embed.author.name)
+            )
+        },
+    );
+    body.push(format!("by {}", embed.author.name));
+    html.push(format!("<p>by {author}</p>"));
+
+    if let Some(description) = embed.description.as_deref().filter(|d| !d.is_empty()) {
+        let spans = markdown::parse(description);
+        body.extend(
+            markdown::plain(&spans)
+                .lines()
+                .map(|line| format!("> {line}")),
+        );
+        html.push(format!("<blockquote>{}</blockquote>", to_html(&spans)));
+    }
+
+    json!({
+        "msgtype": "m.notice",
+        "body": body.join("\n"),
+        "format": "org.matrix.custom.html",
+        "formatted_body": html.concat(),
+    })
+}
+
+fn to_html(spans: &[Span]) -> String {
+    spans
+        .iter()
+        .map(|span| match span {
+            Span::Text(text) => escape(text).replace('\n', "<br>"),
+            Span::Bold(inner) | Span::Heading(inner) => {
+                format!("<strong>{}</strong>", to_html(inner))
+            }
+            Span::Italic(inner) => format!("<em>{}</em>", to_html(inner)),
+            Span::Strike(inner) => format!("<del>{}</del>", to_html(inner)),
+            Span::Code(code) => format!("<code>{}</code>", escape(code)),
+            Span::CodeBlock(code) => format!("<pre><code>{}</code></pre>", escape(code)),
+            Span::Link { text, url } => {
+                format!("<a href=\"{}\">{}</a>", escape(url), to_html(text))
+            }
+        })
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{
+        Json, Router,
+        extract::{Path, State},
+        http::{HeaderMap, header::AUTHORIZATION},
+        routing::put,
+    };
+    use serde_json::{Value, json};
+
+    use crate::{
+        config::FileConfig,
+        delivery::{self, RetryPolicy},
+        formats::Format,
+    };
+
+    type Received = Arc<Mutex<Vec<(String, String, String, Value)>>>;
+
+    /// Just enough of a homeserver to accept a message.
+    async fn send_message(
+        State(received): State<Received>,
+        Path((room, txn)): Path<(String, String)>,
+        headers: HeaderMap,
+        Json(body): Json<Value>,
+    ) -> Json<Value> {
+        let token = headers[AUTHORIZATION].to_str().unwrap().to_string();
+        received.lock().unwrap().push((room, txn, token, body));
+        Json(json!({ "event_id": "$event" }))
+    }
+
+    #[tokio::test]
+    #[allow(clippy::literal_string_with_formatting_args)] // axum path parameters
+    async fn test_local_homeserver_receives_the_message() {
+        let received = Received::default();
+        let app = Router::new()
+            .route(
+                "/_matrix/client/v3/rooms/{room}/send/m.room.message/{txn}",
+                put(send_message),
+            )
+            .with_state(Arc::clone(&received));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let config: FileConfig = format!(
+            r#"
+            error_destination = "matrix"
+            [destinations.matrix]
+            url = "http://{addr}/"
+            format = "matrix"
+            room = "!community:localhost"
+            token = "secret"
+            "#
+        )
+        .parse()
+        .expect("config is valid");
+        let message = json!({ "embeds": [{ "title": "hello", "url": "https://example.com" }] });
+        let request = config
+            .destination("matrix")
+            .expect("destination exists")
+            .request(&message, None, "delivery-1");
+        delivery::send(&reqwest::Client::new(), &request, &RetryPolicy::default())
+            .await
+            .expect("homeserver accepts the message");
+
+        let (room, txn, token, body) = received
+            .lock()
+            .unwrap()
+            .pop()
+            .expect("homeserver got the message");
+        assert_eq!(
+            (room.as_str(), txn.as_str(), token.as_str()),
+            ("!community:localhost", "delivery-1", "Bearer secret")
+        );
+        assert_eq!(body["msgtype"], "m.notice");
+    }
+
+    #[test]
+    fn test_room_id_is_encoded() {
+        let config: FileConfig = r#"
+            error_destination = "matrix"
+            [destinations.matrix]
+            url = "https://matrix.example.com/"
+            format = "matrix"
+            room = "!comm/unity?:example.com"
+            token = "secret"
+        "#
+        .parse()
+        .expect("config is valid");
+        let request = config
+            .destination("matrix")
+            .expect("destination exists")
+            .request(&json!({}), None, "delivery-1");
+        assert_eq!(
+            request.url,
+            "https://matrix.example.com/_matrix/client/v3/rooms/!comm%2Funity%3F:example.com/send/m.room.message/delivery-1"
+        );
+    }
+
+    #[test]
+    fn test_matrix_destinations_need_a_room_and_token() {
+        let config = r#"
+            error_destination = "matrix"
+            [destinations.matrix]
+            url = "https://matrix.example.com"
+            format = "matrix"
+            token = "secret"
+        "#
+        .parse::<FileConfig>();
+        assert!(config.is_err(), "matrix destinations need a room");
+    }
+
+    #[test]
+    fn test_embed_becomes_notice() {
+        let message = json!({
+            "embeds": [{
+                "title": "[catppuccin/java] New comment on issue #20: <Reconsider>",
+                "url": "https://github.com/catppuccin/java/issues/20#issuecomment-1",
+                "description": "**Author:** sgoudham\nsee [the docs](https://example.com)",
+                "color": 0x00a6_e3a1,
+                "author": {
+                    "name": "sgoudham",
+                    "url": "https://github.com/sgoudham",
+                    "icon_url": "https://avatars.githubusercontent.com/u/58985301?v=4",
+                },
+            }],
+        });
+        assert_eq!(
+            Format::Matrix.render(&message, None),
+            json!({
+                "msgtype": "m.notice",
+                "body": "[catppuccin/java] New comment on issue #20: <Reconsider>\n\
+                    https://github.com/catppuccin/java/issues/20#issuecomment-1\n\
+                    by sgoudham\n\
+                    > Author: sgoudham\n\
+                    > see the docs",
+                "format": "org.matrix.custom.html",
+                "formatted_body": "<p><strong><a href=\"https://github.com/catppuccin/java/issues/20#issuecomment-1\">\
+                    [catppuccin/java] New comment on issue #20: &lt;Reconsider&gt;</a></strong></p>\
+                    <p>by <a href=\"https://github.com/sgoudham\">sgoudham</a></p>\
+                    <blockquote><strong>Author:</strong> sgoudham<br>\
+                    see <a href=\"https://example.com\">the docs</a></blockquote>",
+            })
+        );
+    }
+}
@@ -0,0 +1,48 @@
+use serde_json::{Value, json};
+
+use super::Embed;
+
+/// A slack-style attachment, which mattermost incoming webhooks still use.
+/// Mattermost's markdown matches discord's closely enough that the
+/// description is sent as it is.
+pub fn render(embed: &Embed) -> Value {
+    json!({
+        "attachments": [{
+            "fallback": embed.title,
+            "color": embed.hex_color(),
+            "author_name": embed.author.name,
+            "author_link": embed.author.url,
+            "author_icon": embed.author.icon_url,
+            "title": embed.title,
+            "title_link": embed.url,
+            "text": embed.description,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::formats::{Format, tests::release_message};
+
+    #[test]
+    fn test_embed_becomes_attachment() {
+        let message = release_message();
+        assert_eq!(
+            Format::Mattermost.render(&message, None),
+            json!({
+                "attachments": [{
+                    "fallback": "[catppuccin/java] New release published: v1.0.0",
+                    "color": "#cba6f7",
+                    "author_name": "sgoudham",
+                    "author_link": "https://github.com/sgoudham",
+                    "author_icon": "https://avatars.githubusercontent.com/u/58985301?v=4",
+                    "title": "[catppuccin/java] New release published: v1.0.0",
+                    "title_link": "https://github.com/catppuccin/java/releases/tag/v1.0.0",
+                    "text": "**Prerelease**\n\n~~old~~ `new`",
+                }],
+            })
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use serde_json::{Value, json};
+
+use super::{
+    Embed,
+    markdown::{self, Span},
+};
+
+/// An attachment with a colour bar, holding block kit blocks for the author,
+/// the linked title and the description.
+pub fn render(embed: &Embed) -> Value {
+    let author = embed.author.url.as_ref().map_or_else(
+        || escape(&embed.author.name),
+        |url| link(url, &escape(&embed.author.name)),
+    );
+    let mut context = vec![];
+    if let Some(icon_url) = &embed.author.icon_url {
+        context.push(json!({
+            "type": "image",
+            "image_url": icon_url,
+            "alt_text": embed.author.name,
+        }));
+    }
+    context.push(json!({ "type": "mrkdwn", "text": author }));
+
+    let mut blocks = vec![
+        json!({ "type": "context", "elements": context }),
+        json!({
+            "type": "section",
+            "text": {
+                "type": "mrkdwn",
+                "text": format!("*{}*", link(&embed.url, &escape(&embed.title))),
+            },
+        }),
+    ];
+    if let Some(description) = embed.description.as_deref().filter(|d| !d.is_empty()) {
+        blocks.push(json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": mrkdwn(&markdown::parse(description)) },
+        }));
+    }
+
+    json!({
+        // shown in notifications, which don't render attachments.
+        "text": embed.title,
+        "attachments": [{
+            "color": embed.hex_color(),
+            "blocks": blocks,
+        }],
+    })
+}
+
+fn mrkdwn(spans: &[Span]) -> String {
+    spans
+        .iter()
+        .map(|span| match span {
+            Span::Text(text) => escape(text),
+            Span::Bold(inner) | Span::Heading(inner) => format!("*{}*", mrkdwn(inner)),
+            Span::Italic(inner) => format!("_{}_", mrkdwn(inner)),
+            Span::Strike(inner) => format!("~{}~", mrkdwn(inner)),
+            Span::Code(code) => format!("`{}`", escape(code)),
+            Span::CodeBlock(code) => format!("```\n{}\n```", escape(code)),
+            // slack doesn't format link text.
+            Span::Link { text, url } => link(url, &escape(&markdown::plain(text))),
+        })
+        .collect()
+}
+
+fn link(url: &str, text: &str) -> String {
+    format!("<{url}|{text}>")
+}
+
+/// Slack only needs these three escaped, and treats anything else literally.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{markdown, mrkdwn};
+    use crate::formats::{Format, tests::release_message};
+
+    #[test]
+    fn test_discord_markdown_becomes_mrkdwn() {
+        assert_eq!(
+            mrkdwn(&markdown::parse(
+                "**Ref:** [`v1.0.0`](https://example.com) & ~~old~~\n```diff\n- a < b\n```"
+            )),
+            "*Ref:* <https://example.com|v1.0.0> &amp; ~old~\n```\n- a &lt; b\n```"
+        );
+    }
+
+    #[test]
+    fn test_embed_becomes_attachment() {
+        let message = release_message();
+        assert_eq!(
+            Format::Slack.render(&message, None),
+            json!({
+                "text": "[catppuccin/java] New release published: v1.0.0",
+                "attachments": [{
+                    "color": "#cba6f7",
+                    "blocks": [
+                        {
+                            "type": "context",
+                            "elements": [
+                                {
+                                    "type": "image",
+                                    "image_url": "https://avatars.githubusercontent.com/u/58985301?v=4",
+                                    "alt_text": "sgoudham",
+                                },
+                                { "type": "mrkdwn", "text": "<https://github.com/sgoudham|sgoudham>" },
+                            ],
+                        },
+                        {
+                            "type": "section",
+                            "text": {
+                                "type": "mrkdwn",
+                                "text": "*<https://github.com/catppuccin/java/releases/tag/v1.0.0|[catppuccin/java] New release published: v1.0.0>*",
+                            },
+                        },
+                        {
+                            "type": "section",
+                            "text": { "type": "mrkdwn", "text": "*Prerelease*\n\n~old~ `new`" },
+                        },
+                    ],
+                }],
+            })
+        );
+    }
+}
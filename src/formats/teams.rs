@@ -0,0 +1,215 @@
+use serde_json::{Value, json};
+
+use super::{
+    Embed,
+    markdown::{self, Span},
+};
+use crate::colors::{COLORS, rgb};
+
+/// How much of the description is shown. Teams cards are read in a narrow
+/// column, so only a preview is worth sending.
+const MAX_PREVIEW_LENGTH: usize = 300;
+/// How far back from the end of a preview it can be cut to end on a word.
+const MAX_WORD_LENGTH: usize = 30;
+
+/// An adaptive card in a message, as teams incoming webhooks and workflows
+/// expect it.
+pub fn render(embed: &Embed) -> Value {
+    let mut author = vec![];
+    if let Some(icon_url) = &embed.author.icon_url {
+        author.push(json!({
+            "type": "Column",
+            "width": "auto",
+            "items": [{
+                "type": "Image",
+                "url": icon_url,
+                "altText": embed.author.name,
+                "size": "Small",
+                "style": "Person",
+            }],
+        }));
+    }
+    author.push(json!({
+        "type": "Column",
+        "width": "stretch",
+        "verticalContentAlignment": "Center",
+        "items": [{
+            "type": "TextBlock",
+            "text": embed.author.url.as_ref().map_or_else(
+                || embed.author.name.clone(),
+                |url| format!("[{}]({url})", embed.author.name),
+            ),
+            "wrap": true,
+        }],
+    }));
+
+    let mut body = vec![
+        json!({ "type": "ColumnSet", "columns": author }),
+        json!({
+            "type": "TextBlock",
+            "text": linked_title(&embed.title, &embed.url),
+            "size": "Medium",
+            "weight": "Bolder",
+            "color": embed.color.map_or("Default", accent),
+            "wrap": true,
+        }),
+    ];
+    if let Some(description) = embed.description.as_deref().filter(|d| !d.is_empty()) {
+        body.push(json!({
+            "type": "TextBlock",
+            "text": preview(&to_teams(&markdown::parse(description))),
+            "wrap": true,
+        }));
+    }
+
+    json!({
+        "type": "message",
+        "attachments": [{
+            "contentType": "application/vnd.microsoft.card.adaptive",
+            "content": {
+                "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                "type": "AdaptiveCard",
+                "version": "1.4",
+                "body": body,
+            },
+        }],
+    })
+}
+
+/// The title as a markdown link. The `[owner/repo]` in front stays outside
+/// the link, where its brackets can't be mistaken for the link text.
+fn linked_title(title: &str, url: &str) -> String {
+    let escape = |text: &str| text.replace('[', "\\[").replace(']', "\\]");
+    match title
+        .strip_prefix('[')
+        .and_then(|title| title.split_once("] "))
+    {
+        Some((scope, title)) => format!("\\[{scope}\\] [{}]({url})", escape(title)),
+        None => format!("[{}]({url})", escape(title)),
+    }
+}
+
+/// The closest of the few colours a card can use to an embed's catppuccin
+/// colour. The greys have no colour of their own, so stay the default.
+fn accent(color: u32) -> &'static str {
+    let channels = |color: u32| color.to_be_bytes().map(i32::from);
+    let [_, r, g, b] = channels(color);
+    [
+        (COLORS.red, "Attention"),
+        (COLORS.maroon, "Attention"),
+        (COLORS.green, "Good"),
+        (COLORS.teal, "Good"),
+        (COLORS.yellow, "Warning"),
+        (COLORS.peach, "Warning"),
+        (COLORS.blue, "Accent"),
+        (COLORS.sapphire, "Accent"),
+        (COLORS.sky, "Accent"),
+        (COLORS.lavender, "Accent"),
+        (COLORS.mauve, "Accent"),
+        (COLORS.overlay2, "Default"),
+        (COLORS.overlay0, "Default"),
+        (COLORS.surface2, "Default"),
+        (COLORS.base, "Default"),
+    ]
+    .into_iter()
+    .min_by_key(|(catppuccin, _)| {
+        let [_, r2, g2, b2] = channels(rgb(*catppuccin));
+        (r - r2).pow(2) + (g - g2).pow(2) + (b - b2).pow(2)
+    })
+    .map_or("Default", |(_, accent)| accent)
+}
+
+/// Cards only understand bold, italics and links, so everything else is
+/// shown as plain text.
+fn to_teams(spans: &[Span]) -> String {
+    spans
+        .iter()
+        .map(|span| match span {
+            Span::Text(text) | Span::Code(text) | Span::CodeBlock(text) => text.clone(),
+            Span::Bold(inner) | Span::Heading(inner) => format!("**{}**", to_teams(inner)),
+            Span::Italic(inner) => format!("_{}_", to_teams(inner)),
+            Span::Strike(inner) => to_teams(inner),
+            Span::Link { text, url } => format!("[{}]({url})", markdown::plain(text)),
+        })
+        .collect()
+}
+
+fn preview(text: &str) -> String {
+    let Some((limit, _)) = text.char_indices().nth(MAX_PREVIEW_LENGTH) else {
+        return text.to_string();
+    };
+    let cut = &text[..limit];
+    // end between words where one ends close enough to the limit.
+    let cut = cut
+        .rfind(char::is_whitespace)
+        .filter(|end| limit - end <= MAX_WORD_LENGTH)
+        .map_or(cut, |end| &cut[..end]);
+    format!("{}...", cut.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MAX_PREVIEW_LENGTH, accent, linked_title, preview};
+    use crate::{
+        colors::{
+            CI_FAILED_COLOR, COMMENT_DELETED_COLOR, SEVERITY_LOW_COLOR, TAG_DELETED_COLOR,
+            WIKI_COLOR, rgb,
+        },
+        formats::{Format, tests::release_message},
+    };
+
+    #[test]
+    fn test_embed_becomes_adaptive_card() {
+        let message = release_message();
+        let card = Format::Teams.render(&message, None);
+        let body = &card["attachments"][0]["content"]["body"];
+        assert_eq!(
+            body[0]["columns"][0]["items"][0]["url"],
+            "https://avatars.githubusercontent.com/u/58985301?v=4"
+        );
+        assert_eq!(
+            body[0]["columns"][1]["items"][0]["text"],
+            "[sgoudham](https://github.com/sgoudham)"
+        );
+        assert_eq!(
+            body[1]["text"],
+            "\\[catppuccin/java\\] [New release published: v1.0.0](https://github.com/catppuccin/java/releases/tag/v1.0.0)"
+        );
+        assert_eq!(body[1]["color"], "Accent");
+        assert_eq!(body[2]["text"], "**Prerelease**\n\nold new");
+    }
+
+    #[test]
+    fn test_colours_map_to_the_closest_accent() {
+        assert_eq!(accent(rgb(CI_FAILED_COLOR)), "Attention");
+        assert_eq!(accent(rgb(SEVERITY_LOW_COLOR)), "Default");
+        assert_eq!(accent(rgb(COMMENT_DELETED_COLOR)), "Default");
+        assert_eq!(accent(rgb(WIKI_COLOR)), "Accent");
+        assert_eq!(accent(rgb(TAG_DELETED_COLOR)), "Warning");
+    }
+
+    #[test]
+    fn test_long_descriptions_are_cut_near_the_limit() {
+        let words = preview(&"word ".repeat(100));
+        assert!(words.ends_with(" word..."));
+        assert!(words.chars().count() <= MAX_PREVIEW_LENGTH + 3);
+
+        let unbroken = preview(&format!("first line\n{}", "ä".repeat(400)));
+        assert_eq!(
+            unbroken,
+            format!("first line\n{}...", "ä".repeat(MAX_PREVIEW_LENGTH - 11))
+        );
+        assert_eq!(preview("short"), "short");
+    }
+
+    #[test]
+    fn test_brackets_in_titles_are_escaped() {
+        assert_eq!(
+            linked_title(
+                "[catppuccin/java] Issue opened: #1 [bug] crash",
+                "https://example.com"
+            ),
+            "\\[catppuccin/java\\] [Issue opened: #1 \\[bug\\] crash](https://example.com)"
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use serde_json::{Value, json};
+
+use super::Embed;
+use crate::store::Source;
+
+/// Zulip rejects longer topics.
+const MAX_TOPIC_LENGTH: usize = 60;
+
+/// The topic and content of a stream message. Each repository gets its own
+/// topic, so the `[owner/repo]` at the start of the title is left out when
+/// it's just the topic again. Zulip's markdown is close enough to discord's
+/// that the description is sent as it is.
+pub fn render(embed: &Embed, source: Option<&Source>) -> Value {
+    let topic = topic(source);
+
+    let heading = match embed
+        .title
+        .strip_prefix('[')
+        .and_then(|title| title.split_once("] "))
+    {
+        Some((scope, title)) if scope == topic => format!("**[{title}]({})**", embed.url),
+        Some((scope, title)) => format!("{scope}: **[{title}]({})**", embed.url),
+        None => format!("**[{}]({})**", embed.title, embed.url),
+    };
+    let author = embed.author.url.as_ref().map_or_else(
+        || embed.author.name.clone(),
+        |url| format!("[{}]({url})", embed.author.name),
+    );
+
+    let mut content = vec![heading, format!("by {author}")];
+    if let Some(description) = embed.description.as_deref().filter(|d| !d.is_empty()) {
+        content.push(String::new());
+        content.push(description.to_string());
+    }
+
+    json!({
+        "topic": topic,
+        "content": content.join("\n"),
+    })
+}
+
+/// The repository, or the organization for events that aren't about one.
+fn topic(source: Option<&Source>) -> String {
+    let payload = source.map_or(&Value::Null, |source| &source.payload);
+    let topic = payload["repository"]["full_name"]
+        .as_str()
+        .or_else(|| payload["organization"]["login"].as_str())
+        .unwrap_or("rockdove");
+    topic.chars().take(MAX_TOPIC_LENGTH).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        config::FileConfig,
+        delivery::{Auth, Body},
+        formats::Format,
+        store::Source,
+    };
+
+    fn message() -> serde_json::Value {
+        json!({
+            "embeds": [{
+                "title": "[catppuccin/java] New comment on issue #20: Reconsider OSSRH Authentication",
+                "url": "https://github.com/catppuccin/java/issues/20#issuecomment-2351090061",
+                "description": "Testing",
+                "author": { "name": "sgoudham", "url": "https://github.com/sgoudham" },
+            }],
+        })
+    }
+
+    fn source() -> Source {
+        Source {
+            event_type: "issue_comment".to_string(),
+            delivery_id: None,
+            payload: json!({ "repository": { "full_name": "catppuccin/java" } }),
+        }
+    }
+
+    #[test]
+    fn test_repository_is_the_topic() {
+        assert_eq!(
+            Format::Zulip.render(&message(), Some(&source())),
+            json!({
+                "topic": "catppuccin/java",
+                "content": "**[New comment on issue #20: Reconsider OSSRH Authentication](https://github.com/catppuccin/java/issues/20#issuecomment-2351090061)**\n\
+                    by [sgoudham](https://github.com/sgoudham)\n\
+                    \n\
+                    Testing",
+            })
+        );
+    }
+
+    #[test]
+    fn test_zulip_destinations_need_a_bot_user() {
+        let config = r#"
+            error_destination = "zulip"
+            [destinations.zulip]
+            url = "https://catppuccin.zulipchat.com"
+            format = "zulip"
+            room = "github"
+            token = "api-key"
+        "#
+        .parse::<FileConfig>();
+        assert!(config.is_err(), "zulip destinations need a bot user");
+    }
+
+    #[test]
+    fn test_stream_message_is_sent_as_a_form() {
+        let config: FileConfig = r#"
+            error_destination = "zulip"
+            [destinations.zulip]
+            url = "https://catppuccin.zulipchat.com/"
+            format = "zulip"
+            room = "github"
+            user = "rockdove-bot@catppuccin.zulipchat.com"
+            token = "api-key"
+        "#
+        .parse()
+        .expect("config is valid");
+        let request = config
+            .destination("zulip")
+            .expect("destination exists")
+            .request(&message(), Some(&source()), "delivery-1");
+
+        assert_eq!(
+            request.url,
+            "https://catppuccin.zulipchat.com/api/v1/messages"
+        );
+        assert_eq!(
+            request.auth,
+            Some(Auth::Basic {
+                user: "rockdove-bot@catppuccin.zulipchat.com".to_string(),
+                password: "api-key".to_string(),
+            })
+        );
+        let Body::Form(fields) = request.body else {
+            panic!("zulip messages are sent as forms");
+        };
+        let field = |name: &str| {
+            fields
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(field("type"), Some("stream"));
+        assert_eq!(field("to"), Some("github"));
+        assert_eq!(field("topic"), Some("catppuccin/java"));
+    }
+}
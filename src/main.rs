@@ -1,51 +1,64 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 mod events;
 
 use axum::{
     Router,
     extract::{FromRef, State},
-    http::HeaderMap,
+    http::{HeaderMap, StatusCode},
     routing::post,
 };
 use axum_github_webhook_extract::{GithubEvent, GithubToken};
-use colors::COLORS;
-use embed_builder::EmbedBuilder;
-use errors::RockdoveError;
-use octocrab::models::{Author, webhook_events::WebhookEvent};
+use config::FileConfig;
+use dedupe::Deliveries;
+use queue::Queue;
+use routing::RouteContext;
+use store::{Source, Store};
 use tower_http::trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer};
 use tracing::{Level, error, info};
 
+mod admin;
 mod colors;
+mod config;
+mod dedupe;
+mod delivery;
 mod embed_builder;
 mod errors;
+mod formats;
+mod queue;
+mod routing;
+mod store;
 
 #[derive(serde::Deserialize)]
 struct Config {
     github_webhook_secret: String,
-    discord_webhook: String,
-    discord_bot_webhook: String,
-    discord_userstyles_webhook: String,
-    discord_error_webhook: String,
     #[serde(default = "default_port")]
     port: u16,
+    #[serde(default = "default_rules_file")]
+    rules_file: String,
+    #[serde(default = "default_database_file")]
+    database_file: String,
+    admin_token: Option<String>,
 }
 
 const fn default_port() -> u16 {
     3000
 }
 
-#[derive(Clone)]
-struct DiscordHooks {
-    normal: String,
-    bot: String,
-    userstyles: String,
-    error: String,
+fn default_rules_file() -> String {
+    "rockdove.toml".to_string()
+}
+
+fn default_database_file() -> String {
+    "rockdove.db".to_string()
 }
 
 #[derive(Clone)]
 struct AppState {
-    discord_hooks: DiscordHooks,
+    config: Arc<FileConfig>,
+    queue: Queue,
+    deliveries: Arc<Deliveries>,
+    admin_token: Option<Arc<str>>,
     github_token: GithubToken,
 }
 
@@ -62,21 +75,28 @@ async fn main() -> anyhow::Result<()> {
         .init();
 
     let config: Config = envy::from_env()?;
+    let file_config = Arc::new(FileConfig::load(&config.rules_file)?);
+    let http = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let store = Store::open(&config.database_file)?;
+    let deliveries = Arc::new(Deliveries::new(file_config.dedupe.clone(), &store)?);
+    let queue = Queue::new(store, &file_config);
+    queue.spawn_workers(&file_config, &http);
 
     let app = Router::new()
         .route("/webhook", post(webhook))
+        .nest("/admin", admin::router())
         .layer(
             TraceLayer::new_for_http()
                 .make_span_with(DefaultMakeSpan::new().level(Level::INFO))
                 .on_response(DefaultOnResponse::new().level(Level::INFO)),
         )
         .with_state(AppState {
-            discord_hooks: DiscordHooks {
-                normal: config.discord_webhook,
-                bot: config.discord_bot_webhook,
-                userstyles: config.discord_userstyles_webhook,
-                error: config.discord_error_webhook,
-            },
+            config: file_config,
+            queue,
+            deliveries,
+            admin_token: config.admin_token.map(Arc::from),
             github_token: GithubToken(Arc::new(config.github_webhook_secret)),
         });
 
@@ -92,134 +112,135 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-enum HookTarget {
-    Normal,
-    Bot,
-    Userstyles,
-    None,
-}
-
 #[allow(clippy::cognitive_complexity)]
 async fn webhook(
     State(app_state): State<AppState>,
     headers: HeaderMap,
-    GithubEvent(payload): GithubEvent<serde_json::Value>,
-) {
+    GithubEvent(mut payload): GithubEvent<serde_json::Value>,
+) -> StatusCode {
     let Some(Ok(event_type)) = headers.get("X-GitHub-Event").map(|v| v.to_str()) else {
         error!("missing or invalid X-GitHub-Event header");
-        return;
+        return StatusCode::BAD_REQUEST;
     };
 
-    info!(?event_type, "received event");
-
-    let event = match WebhookEvent::try_from_header_and_body(event_type, &payload.to_string()) {
-        Ok(event) => event,
-        Err(e) => {
-            error!(%e, "failed to parse event");
-            return;
-        }
-    };
-
-    let hook = match hook_target(&event) {
-        HookTarget::Normal => {
-            info!("hook target is normal");
-            &app_state.discord_hooks.normal
-        }
-        HookTarget::Bot => {
-            info!("hook target is bot");
-            &app_state.discord_hooks.bot
-        }
-        HookTarget::Userstyles => {
-            info!("hook target is userstyles");
-            &app_state.discord_hooks.userstyles
-        }
-        HookTarget::None => {
-            info!("no target - ignoring event");
-            return;
-        }
-    };
+    let delivery_id = headers
+        .get("X-GitHub-Delivery")
+        .and_then(|v| v.to_str().ok());
 
-    match events::make_embed(event) {
-        Ok(Some(msg)) => send_hook(&msg, hook).await,
-        Ok(None) => info!("no embed created - ignoring event"),
-        Err(e) => {
-            error!(%e, "failed to make discord message");
-            send_error_hook(&e, &app_state.discord_hooks.error).await;
-        }
-    }
-}
+    info!(?event_type, ?delivery_id, "received event");
 
-fn hook_target(event: &WebhookEvent) -> HookTarget {
-    if let Some(sender) = &event.sender
-        && sender.r#type == "Bot"
+    let replay = app_state.deliveries.allows_replay_header()
+        && headers
+            .get("X-Rockdove-Replay")
+            .is_some_and(|v| v.as_bytes().eq_ignore_ascii_case(b"true"));
+    if let Some(delivery_id) = delivery_id
+        && !replay
+        && !app_state.deliveries.claim(delivery_id)
     {
-        return HookTarget::Bot;
+        info!(delivery_id, "already handled delivery - ignoring event");
+        return StatusCode::OK;
     }
 
-    if let Some(repository) = &event.repository {
-        // userstyles is a monorepo with a lot of activity so we're adding a separate redirect for it.
-        if repository.name == "userstyles" {
-            return HookTarget::Userstyles;
-        }
-
-        if repository.private.unwrap_or(false) {
-            info!("ignoring private repository event");
-            return HookTarget::None;
+    events::redact(event_type, &mut payload);
+    let source = Source {
+        event_type: event_type.to_string(),
+        delivery_id: delivery_id.map(ToString::to_string),
+        payload,
+    };
+    if let Err(e) = dispatch(&app_state, &source, None) {
+        error!(%e, "failed to queue event");
+        if let Some(delivery_id) = delivery_id {
+            app_state.deliveries.release(delivery_id);
         }
+        return StatusCode::INTERNAL_SERVER_ERROR;
     }
 
-    HookTarget::Normal
+    StatusCode::OK
 }
 
-async fn send_hook(e: &serde_json::Value, hook: &str) {
-    match reqwest::Client::new().post(hook).json(e).send().await {
-        Err(e) => error!(%e, "failed to send hook"),
-        Ok(r) => match r.error_for_status() {
-            Err(e) => {
-                error!(%e, "hook failed");
-            }
-            _ => {
-                info!("hook sent");
-            }
-        },
-    }
+/// What became of a dispatched event.
+#[derive(Debug, PartialEq, Eq)]
+enum Dispatched {
+    /// Queued for at least one destination.
+    Queued,
+    /// Not routed anywhere, or not worth a message.
+    Ignored,
+    /// Kept as a new dead letter, since it couldn't be rendered.
+    DeadLettered,
 }
 
-async fn send_error_hook(e: &RockdoveError, hook: &str) {
-    let mut embed = EmbedBuilder::default();
-    embed.title("Error");
-    embed.description(&e.to_string());
-    embed.color(COLORS.red);
-    embed.author(make_hammy());
-    embed.url("https://goudham.com/");
-    let msg = embed
-        .try_build()
-        .expect("error embed should always be valid");
-    send_hook(&msg, hook).await;
-}
+/// Routes, renders and queues an event. Delivery happens in the background,
+/// so once this returns the event is safe. `only` limits delivery to a single
+/// destination, for replaying a failed delivery.
+#[allow(clippy::cognitive_complexity)]
+fn dispatch(
+    app_state: &AppState,
+    source: &Source,
+    only: Option<&str>,
+) -> store::Result<Dispatched> {
+    let destinations: Vec<&str> = app_state
+        .config
+        .route(&RouteContext::new(&source.event_type, &source.payload))
+        .into_iter()
+        .map(|(name, _)| name)
+        .filter(|name| only.is_none_or(|only| only == *name))
+        .collect();
+    if destinations.is_empty() {
+        info!("no target - ignoring event");
+        return Ok(Dispatched::Ignored);
+    }
+    info!(?destinations, "hook targets");
 
-fn make_hammy() -> Author {
-    serde_json::from_value(serde_json::json!({
-      "login": "sgoudham",
-      "id": 58_985_301,
-      "node_id": "MDQ6VXNlcjU4OTg1MzAx",
-      "avatar_url": "https://avatars.githubusercontent.com/u/58985301?v=4",
-      "gravatar_id": "",
-      "url": "https://api.github.com/users/sgoudham",
-      "html_url": "https://github.com/sgoudham",
-      "followers_url": "https://api.github.com/users/sgoudham/followers",
-      "following_url": "https://api.github.com/users/sgoudham/following{/other_user}",
-      "gists_url": "https://api.github.com/users/sgoudham/gists{/gist_id}",
-      "starred_url": "https://api.github.com/users/sgoudham/starred{/owner}{/repo}",
-      "subscriptions_url": "https://api.github.com/users/sgoudham/subscriptions",
-      "organizations_url": "https://api.github.com/users/sgoudham/orgs",
-      "repos_url": "https://api.github.com/users/sgoudham/repos",
-      "events_url": "https://api.github.com/users/sgoudham/events{/privacy}",
-      "received_events_url": "https://api.github.com/users/sgoudham/received_events",
-      "type": "User",
-      "site_admin": false
-    }))
-    .expect("hammy is always valid :pepe_heart:")
+    let event = match events::parse(&source.event_type, &source.payload.to_string()) {
+        Ok(event) => event,
+        Err(e) => {
+            error!(%e, "failed to parse event");
+            app_state
+                .queue
+                .store()
+                .push_dead_letter(source, None, &e.to_string())?;
+            return Ok(Dispatched::DeadLettered);
+        }
+    };
+
+    let ctx = events::Context {
+        settings: &app_state.config.events,
+        store: app_state.queue.store(),
+        payload: &source.payload,
+        replay: only.is_some(),
+    };
+    match events::make_embed(event, &ctx) {
+        Ok(Some(msg)) => {
+            app_state.queue.push(
+                &destinations,
+                &msg.body,
+                msg.tracking.as_ref(),
+                Some(source),
+                &msg.state,
+            )?;
+            info!(?destinations, "hooks queued");
+            Ok(Dispatched::Queued)
+        }
+        Ok(None) => {
+            info!("no embed created - ignoring event");
+            Ok(Dispatched::Ignored)
+        }
+        Err(e) => {
+            error!(%e, "failed to make discord message");
+            app_state
+                .queue
+                .store()
+                .push_dead_letter(source, None, &e.to_string())?;
+            app_state.queue.push(
+                &[&app_state.config.error_destination],
+                &e.to_embed(),
+                None,
+                None,
+                &[],
+            )?;
+            Ok(Dispatched::DeadLettered)
+        }
+    }
 }
 
 #[cfg(test)]
@@ -234,8 +255,7 @@ mod tests {
 
     impl TestConfig {
         pub fn new(event_type: &str, payload: &str) -> Self {
-            let event = WebhookEvent::try_from_header_and_body(event_type, payload)
-                .expect("event fixture is valid");
+            let event = crate::events::parse(event_type, payload).expect("event fixture is valid");
             let mut settings = insta::Settings::new();
             settings.set_omit_expression(true);
             settings.set_snapshot_path(format!("../../snapshots/{event_type}"));
@@ -247,6 +267,42 @@ mod tests {
         }
     }
 
+    /// Renders a fixture, for tests that check whether an embed is made at all.
+    pub fn render(
+        event_type: &str,
+        fixture: &str,
+        settings: &crate::events::Settings,
+        store: &crate::store::Store,
+    ) -> Option<serde_json::Value> {
+        render_message(event_type, fixture, settings, store).map(|msg| msg.body)
+    }
+
+    /// Renders a fixture, keeping everything that goes in the queue.
+    pub fn render_message(
+        event_type: &str,
+        fixture: &str,
+        settings: &crate::events::Settings,
+        store: &crate::store::Store,
+    ) -> Option<crate::events::Message> {
+        let filename = format!(
+            "{}/fixtures/{event_type}/{fixture}.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let payload = std::fs::read_to_string(&filename).expect("fixture exists");
+        let event = crate::events::parse(event_type, &payload).expect("event fixture is valid");
+        let payload = serde_json::from_str(&payload).expect("fixture is valid json");
+        let ctx = crate::events::Context {
+            settings,
+            store,
+            payload: &payload,
+            replay: false,
+        };
+        let message = crate::events::make_embed(event, &ctx).expect("make_embed should succeed")?;
+        // stand in for the queue, which saves the state along with the jobs.
+        store.set_states(&message.state).expect("state is saved");
+        Some(message)
+    }
+
     pub fn embed_context(embed: &serde_json::Value) -> serde_json::Value {
         json!({
             "author_name_length": &embed["embeds"][0]["author"]["name"].as_str().unwrap().len(),
@@ -259,6 +315,13 @@ mod tests {
     #[macro_export]
     macro_rules! snapshot_test {
         ($event:literal, $event_type:expr) => {
+            $crate::snapshot_test!($event, $event_type, &$crate::events::Settings::default());
+        };
+        ($event:literal, $event_type:expr, $settings:expr) => {
+            let store = $crate::store::Store::open_in_memory().expect("in-memory store opens");
+            $crate::snapshot_test!($event, $event_type, $settings, &store);
+        };
+        ($event:literal, $event_type:expr, $settings:expr, $store:expr) => {
             let filename = format!(
                 "{}/fixtures/{}/{}.json",
                 env!("CARGO_MANIFEST_DIR"),
@@ -271,9 +334,18 @@ mod tests {
                 mut settings,
             } = $crate::tests::TestConfig::new($event, &payload);
 
-            let embed = $crate::events::make_embed(webhook_event)
+            let payload: serde_json::Value =
+                serde_json::from_str(&payload).expect("fixture is valid json");
+            let ctx = $crate::events::Context {
+                settings: $settings,
+                store: $store,
+                payload: &payload,
+                replay: false,
+            };
+            let embed = $crate::events::make_embed(webhook_event, &ctx)
                 .expect("make_embed should succeed")
-                .expect("event fixture can be turned into an embed");
+                .expect("event fixture can be turned into an embed")
+                .body;
 
             settings.set_info(&$crate::tests::embed_context(&embed));
             settings.bind(|| insta::assert_yaml_snapshot!(embed));
@@ -0,0 +1,202 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use tokio::sync::Notify;
+use tracing::{error, info};
+
+use crate::{
+    config::FileConfig,
+    delivery,
+    errors::RockdoveError,
+    store::{self, Job, Source, Store, Tracking},
+};
+
+/// Sits between rendering and delivery so that an event is safe as soon as
+/// it's persisted, whether or not discord is reachable.
+#[derive(Clone)]
+pub struct Queue {
+    store: Store,
+    wakers: Arc<HashMap<String, Notify>>,
+}
+
+impl Queue {
+    pub fn new(store: Store, config: &FileConfig) -> Self {
+        let wakers = config
+            .destinations
+            .keys()
+            .map(|name| (name.clone(), Notify::new()))
+            .collect();
+        Self {
+            store,
+            wakers: Arc::new(wakers),
+        }
+    }
+
+    pub const fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// Queues the message for every destination, or for none of them if it
+    /// fails.
+    pub fn push(
+        &self,
+        destinations: &[&str],
+        message: &serde_json::Value,
+        tracking: Option<&Tracking>,
+        source: Option<&Source>,
+        state: &[(String, String)],
+    ) -> store::Result<()> {
+        self.store
+            .push_jobs(destinations, message, tracking, source, state)?;
+        for destination in destinations {
+            if let Some(waker) = self.wakers.get(*destination) {
+                waker.notify_one();
+            }
+        }
+        Ok(())
+    }
+
+    /// Starts one worker per destination. Each worker delivers its jobs
+    /// strictly in order, starting with whatever was left over from the last
+    /// run.
+    pub fn spawn_workers(&self, config: &Arc<FileConfig>, http: &reqwest::Client) {
+        for destination in self.wakers.keys() {
+            let worker = Worker {
+                queue: self.clone(),
+                config: Arc::clone(config),
+                http: http.clone(),
+                destination: destination.clone(),
+            };
+            tokio::spawn(worker.run());
+        }
+    }
+}
+
+struct Worker {
+    queue: Queue,
+    config: Arc<FileConfig>,
+    http: reqwest::Client,
+    destination: String,
+}
+
+impl Worker {
+    async fn run(self) {
+        loop {
+            match self.queue.store.next_job(&self.destination) {
+                Ok(Some(job)) => {
+                    self.deliver(&job).await;
+                    if let Err(e) = self.queue.store.complete_job(job.id) {
+                        error!(%e, destination = self.destination, "failed to complete job");
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                    }
+                }
+                Ok(None) => self.queue.wakers[&self.destination].notified().await,
+                Err(e) => {
+                    error!(%e, destination = self.destination, "failed to read queue");
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+            }
+        }
+    }
+
+    async fn deliver(&self, job: &Job) {
+        let destination = job.destination.as_str();
+        let Some(hook) = self.config.destination(destination) else {
+            error!(destination, "dropping job for unknown destination");
+            return;
+        };
+
+        let sent = match &job.tracking {
+            Some(tracking) if hook.format().can_edit() => {
+                self.send_tracked(&job.message, hook.url(), tracking).await
+            }
+            // an edit can't replace the original here, so it would look like
+            // a new announcement.
+            Some(Tracking::Edit(_)) => {
+                info!(destination, job = job.id, format = ?hook.format(), "dropping edit");
+                return;
+            }
+            _ => {
+                let request = hook.request(&job.message, job.source.as_ref(), &transaction_id(job));
+                delivery::send(&self.http, &request, &self.config.delivery).await
+            }
+        };
+        match sent {
+            Ok(()) => info!(destination, job = job.id, "hook sent"),
+            Err(source) => {
+                error!(%source, destination, job = job.id, "hook failed");
+                if let Some(event) = &job.source
+                    && let Err(e) = self.queue.store.push_dead_letter(
+                        event,
+                        Some(destination),
+                        &source.to_string(),
+                    )
+                {
+                    error!(%e, "failed to store dead letter");
+                }
+                // a broken error hook can't report its own failures.
+                if destination == self.config.error_destination {
+                    return;
+                }
+                let e = RockdoveError::Delivery {
+                    destination: destination.to_string(),
+                    source,
+                };
+                if let Err(e) = self.queue.push(
+                    &[&self.config.error_destination],
+                    &e.to_embed(),
+                    None,
+                    None,
+                    &[],
+                ) {
+                    error!(%e, "failed to queue error hook");
+                }
+            }
+        }
+    }
+
+    /// Posts a message and remembers its id, or edits the message remembered
+    /// under the same key. Edits of a message this destination never got are
+    /// dropped, since posting them would look like a new announcement.
+    async fn send_tracked(
+        &self,
+        message: &serde_json::Value,
+        url: &str,
+        tracking: &Tracking,
+    ) -> delivery::Result<()> {
+        let policy = &self.config.delivery;
+        match tracking {
+            Tracking::Remember(key) => {
+                let id = delivery::post(&self.http, message, url, policy).await?;
+                if let Err(e) = self.queue.store.set_state(&self.message_key(key), &id) {
+                    error!(%e, destination = self.destination, "failed to remember message");
+                }
+                Ok(())
+            }
+            Tracking::Edit(key) => match self.queue.store.state(&self.message_key(key)) {
+                Ok(Some(id)) => delivery::edit(&self.http, message, url, &id, policy).await,
+                Ok(None) => {
+                    info!(destination = self.destination, key, "no message to edit");
+                    Ok(())
+                }
+                Err(e) => {
+                    error!(%e, destination = self.destination, "failed to look up message");
+                    Ok(())
+                }
+            },
+        }
+    }
+
+    fn message_key(&self, key: &str) -> String {
+        format!("message:{}:{key}", self.destination)
+    }
+}
+
+/// Github's delivery id, so that a destination which deduplicates by
+/// transaction id ignores a redelivered or replayed event that it already
+/// posted. Messages rockdove made up itself fall back to the job id.
+fn transaction_id(job: &Job) -> String {
+    job.source
+        .as_ref()
+        .and_then(|source| source.delivery_id.clone())
+        .unwrap_or_else(|| format!("rockdove-job-{}", job.id))
+}
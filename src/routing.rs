@@ -0,0 +1,413 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+/// A single entry in the ordered `rules` list. The first rule whose filter
+/// matches an event decides where it goes; an empty `destinations` list drops
+/// the event. A matching rule with `fallthrough` set adds its destinations and
+/// keeps looking, which is how one event fans out to several destinations.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    #[serde(default, rename = "match")]
+    pub filter: Filter,
+    pub destinations: Vec<String>,
+    #[serde(default)]
+    pub fallthrough: bool,
+}
+
+/// Destinations for events that mustn't end up in a public channel because of
+/// a catch-all rule. These events skip every rule that doesn't name their
+/// event type in its `match` or drop events, and otherwise only go to their
+/// destination.
+/// Unset destinations default to a destination of the same name, or drop the
+/// events if there isn't one.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Restricted {
+    /// Dependabot, code scanning and secret scanning alerts, and repository
+    /// advisories.
+    pub security: Option<String>,
+    /// Organization membership, teams, repositories being made public or
+    /// private, and the webhook itself being created or deleted.
+    pub admin: Option<String>,
+    /// Deleted issue and pull request comments.
+    pub moderation: Option<String>,
+}
+
+impl Restricted {
+    /// The destinations that were set explicitly, and so must exist.
+    pub fn named(&self) -> impl Iterator<Item = &String> {
+        [&self.security, &self.admin, &self.moderation]
+            .into_iter()
+            .flatten()
+    }
+
+    /// Every destination that restricted events can go to.
+    pub fn destinations(&self) -> impl Iterator<Item = &str> {
+        [
+            (&self.security, "security"),
+            (&self.admin, "admin"),
+            (&self.moderation, "moderators"),
+        ]
+        .into_iter()
+        .map(|(name, default)| name.as_deref().unwrap_or(default))
+    }
+
+    /// The only destination allowed to receive the event, if it's restricted.
+    pub fn destination(&self, ctx: &RouteContext) -> Option<&str> {
+        let (name, default) = match ctx.event {
+            "dependabot_alert"
+            | "code_scanning_alert"
+            | "secret_scanning_alert"
+            | "repository_advisory" => (&self.security, "security"),
+            "organization" | "team" | "public" | "ping" | "meta" => (&self.admin, "admin"),
+            "repository" if matches!(ctx.action, Some("privatized" | "publicized")) => {
+                (&self.admin, "admin")
+            }
+            "issue_comment" | "pull_request_review_comment" if ctx.action == Some("deleted") => {
+                (&self.moderation, "moderators")
+            }
+            _ => return None,
+        };
+        Some(name.as_deref().unwrap_or(default))
+    }
+}
+
+/// Every field is a list of accepted values. An empty list accepts anything,
+/// so an empty filter matches every event.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Filter {
+    /// Matched against both the repository name and its full name.
+    #[serde(default)]
+    pub repository: Vec<String>,
+    #[serde(default)]
+    pub owner: Vec<String>,
+    /// The `X-GitHub-Event` header, e.g. `pull_request`.
+    #[serde(default)]
+    pub event: Vec<String>,
+    #[serde(default)]
+    pub action: Vec<String>,
+    /// `User`, `Bot` or `Organization`.
+    #[serde(default)]
+    pub sender_type: Vec<String>,
+    #[serde(default)]
+    pub sender_login: Vec<String>,
+    /// Matches if the issue, pull request or discussion has any of these labels.
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// `public`, `private` or `internal`.
+    #[serde(default)]
+    pub visibility: Vec<String>,
+}
+
+/// The parts of an incoming event that rules can match on, pulled out of the
+/// raw payload so that routing works for every event type, even ones we can't
+/// render.
+#[derive(Debug, Default)]
+pub struct RouteContext<'a> {
+    pub event: &'a str,
+    pub action: Option<&'a str>,
+    pub repository: Option<&'a str>,
+    pub repository_full_name: Option<&'a str>,
+    pub owner: Option<&'a str>,
+    pub sender_type: Option<&'a str>,
+    pub sender_login: Option<&'a str>,
+    pub labels: Vec<&'a str>,
+    pub visibility: Option<&'a str>,
+}
+
+impl<'a> RouteContext<'a> {
+    pub fn new(event: &'a str, payload: &'a Value) -> Self {
+        let repository = &payload["repository"];
+        let labels = ["issue", "pull_request", "discussion"]
+            .iter()
+            .filter_map(|target| payload[target]["labels"].as_array())
+            .flatten()
+            .chain(std::iter::once(&payload["label"]))
+            .filter_map(|label| label["name"].as_str())
+            .collect();
+        let visibility = repository["visibility"].as_str().or_else(|| {
+            repository["private"]
+                .as_bool()
+                .map(|private| if private { "private" } else { "public" })
+        });
+
+        Self {
+            event,
+            action: payload["action"].as_str(),
+            repository: repository["name"].as_str(),
+            repository_full_name: repository["full_name"].as_str(),
+            owner: repository["owner"]["login"]
+                .as_str()
+                .or_else(|| payload["organization"]["login"].as_str()),
+            sender_type: payload["sender"]["type"].as_str(),
+            sender_login: payload["sender"]["login"].as_str(),
+            labels,
+            visibility,
+        }
+    }
+}
+
+impl Filter {
+    pub fn matches(&self, ctx: &RouteContext) -> bool {
+        (self.repository.is_empty()
+            || accepts(&self.repository, ctx.repository)
+            || accepts(&self.repository, ctx.repository_full_name))
+            && accepts(&self.owner, ctx.owner)
+            && accepts(&self.event, Some(ctx.event))
+            && accepts(&self.action, ctx.action)
+            && accepts(&self.sender_type, ctx.sender_type)
+            && accepts(&self.sender_login, ctx.sender_login)
+            && (self.labels.is_empty() || ctx.labels.iter().any(|l| accepts(&self.labels, Some(l))))
+            && accepts(&self.visibility, ctx.visibility)
+    }
+}
+
+fn accepts(allowed: &[String], value: Option<&str>) -> bool {
+    allowed.is_empty() || value.is_some_and(|value| allowed.iter().any(|a| a == value))
+}
+
+/// Collects the destination names of every matching rule up to and including
+/// the first one without `fallthrough`, without duplicates.
+pub fn route<'a>(rules: impl IntoIterator<Item = &'a Rule>, ctx: &RouteContext) -> Vec<&'a str> {
+    let mut destinations = vec![];
+    for rule in rules.into_iter().filter(|rule| rule.filter.matches(ctx)) {
+        for name in &rule.destinations {
+            if !destinations.contains(&name.as_str()) {
+                destinations.push(name.as_str());
+            }
+        }
+        if !rule.fallthrough {
+            break;
+        }
+    }
+    destinations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RouteContext;
+    use crate::config::FileConfig;
+
+    fn destinations(event: &str, fixture: &str) -> Vec<String> {
+        destinations_with(include_str!("../rockdove.toml"), event, fixture)
+    }
+
+    fn destinations_with(config: &str, event: &str, fixture: &str) -> Vec<String> {
+        let config: FileConfig = config.parse().expect("config is valid");
+        let filename = format!(
+            "{}/fixtures/{event}/{fixture}.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let payload: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(filename).expect("fixture exists"))
+                .expect("fixture is valid json");
+        config
+            .route(&RouteContext::new(event, &payload))
+            .into_iter()
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+
+    const FAN_OUT: &str = r#"
+        error_destination = "main"
+        [destinations.main]
+        url = "https://example.com/main"
+        [destinations.announcements]
+        url = "https://example.com/announcements"
+        match = { event = ["release"] }
+        [destinations.maintainers]
+        url = "https://example.com/maintainers"
+        [[rules]]
+        destinations = ["announcements", "main"]
+        fallthrough = true
+        [[rules]]
+        match = { sender_type = ["Bot"] }
+        destinations = []
+        [[rules]]
+        destinations = ["main", "maintainers"]
+    "#;
+
+    #[test]
+    fn test_normal_events_go_to_main_feed() {
+        assert_eq!(destinations("pull_request", "opened"), ["normal"]);
+    }
+
+    #[test]
+    fn test_bot_events_go_to_bot_feed() {
+        assert_eq!(destinations("pull_request", "opened_by_bot"), ["bot"]);
+    }
+
+    #[test]
+    fn test_userstyles_events_go_to_userstyles_feed() {
+        assert_eq!(
+            destinations("pull_request", "userstyles_review"),
+            ["userstyles"]
+        );
+    }
+
+    #[test]
+    fn test_security_events_skip_the_rules() {
+        assert_eq!(
+            destinations("secret_scanning_alert", "created"),
+            ["security"]
+        );
+        assert!(
+            destinations_with(FAN_OUT, "dependabot_alert", "created").is_empty(),
+            "security events are dropped without a security destination"
+        );
+    }
+
+    #[test]
+    fn test_rules_naming_a_restricted_event_take_precedence() {
+        let config = r#"
+            error_destination = "main"
+            [destinations.main]
+            url = "https://example.com/main"
+            [destinations.security]
+            url = "https://example.com/security"
+            [destinations.maintainers]
+            url = "https://example.com/maintainers"
+            [[rules]]
+            match = { event = ["dependabot_alert"] }
+            destinations = ["maintainers"]
+            [[rules]]
+            destinations = ["main"]
+        "#;
+        assert_eq!(
+            destinations_with(config, "dependabot_alert", "created"),
+            ["maintainers"]
+        );
+        assert_eq!(
+            destinations_with(config, "secret_scanning_alert", "created"),
+            ["security"],
+            "catch-all rules still can't leak restricted events"
+        );
+    }
+
+    #[test]
+    fn test_drop_rules_apply_to_security_events() {
+        assert!(
+            destinations("secret_scanning_alert", "created_private").is_empty(),
+            "alerts from private repositories are dropped like everything else"
+        );
+    }
+
+    #[test]
+    fn test_org_events_go_to_admin_feed() {
+        assert_eq!(destinations("organization", "member_added"), ["admin"]);
+        assert_eq!(destinations("repository", "privatized"), ["admin"]);
+        assert_eq!(destinations("ping", "organization"), ["admin"]);
+        assert_eq!(destinations("repository", "created"), ["normal"]);
+    }
+
+    #[test]
+    fn test_deleted_comments_go_to_moderators() {
+        assert_eq!(destinations("issue_comment", "deleted"), ["moderators"]);
+        assert_eq!(destinations("issue_comment", "created"), ["normal"]);
+        assert_eq!(
+            destinations("pull_request_review_comment", "deleted"),
+            ["moderators"]
+        );
+    }
+
+    #[test]
+    fn test_deleted_comments_on_private_repositories_are_dropped() {
+        assert!(destinations("issue_comment", "deleted_private").is_empty());
+    }
+
+    #[test]
+    fn test_fallthrough_fans_out_to_every_matching_rule() {
+        assert_eq!(
+            destinations_with(FAN_OUT, "release", "released"),
+            ["announcements", "main", "maintainers"]
+        );
+    }
+
+    #[test]
+    fn test_destination_filter_is_applied() {
+        assert_eq!(
+            destinations_with(FAN_OUT, "issues", "opened"),
+            ["main", "maintainers"]
+        );
+    }
+
+    #[test]
+    fn test_fan_out_stops_at_first_rule_without_fallthrough() {
+        assert_eq!(
+            destinations_with(FAN_OUT, "pull_request", "opened_by_bot"),
+            ["main"]
+        );
+    }
+
+    #[test]
+    fn test_unknown_destination_is_rejected() {
+        let config = r#"
+            error_destination = "error"
+            [destinations.error]
+            url = "https://example.com"
+            [[rules]]
+            destinations = ["missing"]
+        "#
+        .parse::<FileConfig>();
+        assert!(
+            config.is_err(),
+            "rules must only reference known destinations"
+        );
+    }
+
+    #[test]
+    fn test_restricted_destinations_without_their_environment_are_disabled() {
+        let path = std::env::temp_dir().join("rockdove-restricted-env.toml");
+        std::fs::write(
+            &path,
+            r#"
+            error_destination = "error"
+            [destinations.error]
+            url = "https://example.com"
+            [destinations.security]
+            url_env = "ROCKDOVE_TEST_UNSET_SECURITY_WEBHOOK"
+            [[rules]]
+            destinations = ["error"]
+            "#,
+        )
+        .unwrap();
+        let config = FileConfig::load(&path).expect("config loads without the security webhook");
+        assert!(config.destination("security").is_none());
+        assert!(config.destination("error").is_some());
+
+        std::fs::write(
+            &path,
+            r#"
+            error_destination = "error"
+            [destinations.error]
+            url_env = "ROCKDOVE_TEST_UNSET_ERROR_WEBHOOK"
+            "#,
+        )
+        .unwrap();
+        assert!(
+            FileConfig::load(&path).is_err(),
+            "other destinations still need their environment"
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_unknown_restricted_destination_is_rejected() {
+        let config = r#"
+            error_destination = "error"
+            [destinations.error]
+            url = "https://example.com"
+            [destinations.security]
+            url = "https://example.com/security"
+            [restricted]
+            security = "securty"
+        "#
+        .parse::<FileConfig>();
+        assert!(
+            config.is_err(),
+            "restricted events mustn't be lost to a typo"
+        );
+    }
+}
@@ -0,0 +1,462 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rusqlite::{Connection, OptionalExtension, Row, params};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Everything rockdove needs to remember across restarts, kept in a single
+/// sqlite database.
+#[derive(Clone)]
+pub struct Store {
+    conn: Arc<Mutex<Connection>>,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The github event that a job or dead letter came from, kept so that it can
+/// be rendered again later.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Source {
+    pub event_type: String,
+    pub delivery_id: Option<String>,
+    pub payload: serde_json::Value,
+}
+
+/// How a message relates to ones posted before it, for messages that are
+/// edited in place later instead of being posted again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Tracking {
+    /// Remember the posted message under this key.
+    Remember(String),
+    /// Edit the message remembered under this key instead of posting.
+    Edit(String),
+}
+
+/// A message waiting to be delivered to a destination.
+#[derive(Debug)]
+pub struct Job {
+    pub id: i64,
+    pub destination: String,
+    pub message: serde_json::Value,
+    pub tracking: Option<Tracking>,
+    /// Missing for messages rockdove made up itself, like error reports.
+    pub source: Option<Source>,
+}
+
+/// An event that couldn't be rendered, or a message that couldn't be
+/// delivered.
+#[derive(Debug, Serialize)]
+pub struct DeadLetter {
+    pub id: i64,
+    pub event_type: String,
+    pub delivery_id: Option<String>,
+    /// Missing if the event failed before it was routed.
+    pub destination: Option<String>,
+    pub error: String,
+    pub created_at: i64,
+    pub payload: serde_json::Value,
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS jobs (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        destination TEXT NOT NULL,
+        message TEXT NOT NULL,
+        source TEXT,
+        tracking TEXT,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS jobs_destination ON jobs (destination, id);
+    CREATE TABLE IF NOT EXISTS dead_letters (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        event_type TEXT NOT NULL,
+        delivery_id TEXT,
+        destination TEXT,
+        error TEXT NOT NULL,
+        payload TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS state (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS deliveries (
+        id TEXT PRIMARY KEY,
+        seen_at INTEGER NOT NULL
+    );
+";
+
+/// Columns added to a table after it was first created. Tables from an older
+/// database get them added in this order, so every database since the jobs
+/// table was introduced can still be opened. The other tables haven't changed
+/// since they were added, so `SCHEMA` creates them as they are.
+const ADDED_COLUMNS: &[(&str, &str, &str)] =
+    &[("jobs", "source", "TEXT"), ("jobs", "tracking", "TEXT")];
+
+impl Store {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        Self::init(conn)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        for (table, column, kind) in ADDED_COLUMNS {
+            let exists = conn
+                .prepare("SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2")?
+                .exists(params![table, column])?;
+            if !exists {
+                conn.execute(
+                    &format!("ALTER TABLE {table} ADD COLUMN {column} {kind}"),
+                    [],
+                )?;
+            }
+        }
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    fn conn(&self) -> MutexGuard<'_, Connection> {
+        // a panic while holding the lock can't leave sqlite in a bad state, so
+        // carry on with the connection regardless.
+        self.conn
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Queues the message for every destination at once, so that a failure
+    /// part way through doesn't leave some of them queued.
+    pub fn push_jobs(
+        &self,
+        destinations: &[&str],
+        message: &serde_json::Value,
+        tracking: Option<&Tracking>,
+        source: Option<&Source>,
+        state: &[(String, String)],
+    ) -> Result<Vec<i64>> {
+        let message = serde_json::to_string(message)?;
+        let tracking = tracking.map(serde_json::to_string).transpose()?;
+        let source = source.map(serde_json::to_string).transpose()?;
+        let mut conn = self.conn();
+        let transaction = conn.transaction()?;
+        let mut ids = Vec::with_capacity(destinations.len());
+        for destination in destinations {
+            transaction.execute(
+                "INSERT INTO jobs (destination, message, tracking, source, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![destination, message, tracking, source, now()],
+            )?;
+            ids.push(transaction.last_insert_rowid());
+        }
+        for (key, value) in state {
+            transaction.execute(SET_STATE, params![key, value, now()])?;
+        }
+        transaction.commit()?;
+        drop(conn);
+        Ok(ids)
+    }
+
+    /// The oldest pending job for a destination.
+    pub fn next_job(&self, destination: &str) -> Result<Option<Job>> {
+        let row = self
+            .conn()
+            .query_row(
+                "SELECT id, message, tracking, source FROM jobs
+                 WHERE destination = ?1 ORDER BY id LIMIT 1",
+                params![destination],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                    ))
+                },
+            )
+            .optional()?;
+        row.map(|(id, message, tracking, source)| {
+            Ok(Job {
+                id,
+                destination: destination.to_string(),
+                message: serde_json::from_str(&message)?,
+                tracking: tracking.as_deref().map(serde_json::from_str).transpose()?,
+                source: source.as_deref().map(serde_json::from_str).transpose()?,
+            })
+        })
+        .transpose()
+    }
+
+    pub fn complete_job(&self, id: i64) -> Result<()> {
+        self.conn()
+            .execute("DELETE FROM jobs WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    pub fn push_dead_letter(
+        &self,
+        source: &Source,
+        destination: Option<&str>,
+        error: &str,
+    ) -> Result<i64> {
+        let conn = self.conn();
+        conn.execute(
+            "INSERT INTO dead_letters (event_type, delivery_id, destination, error, payload, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                source.event_type,
+                source.delivery_id,
+                destination,
+                error,
+                serde_json::to_string(&source.payload)?,
+                now(),
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Every dead letter, oldest first.
+    pub fn dead_letters(&self) -> Result<Vec<DeadLetter>> {
+        let rows = self
+            .conn()
+            .prepare(&format!("{SELECT_DEAD_LETTER} ORDER BY id"))?
+            .query_map([], dead_letter_columns)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        rows.into_iter().map(DeadLetterRow::parse).collect()
+    }
+
+    pub fn dead_letter(&self, id: i64) -> Result<Option<DeadLetter>> {
+        self.conn()
+            .query_row(
+                &format!("{SELECT_DEAD_LETTER} WHERE id = ?1"),
+                params![id],
+                dead_letter_columns,
+            )
+            .optional()?
+            .map(DeadLetterRow::parse)
+            .transpose()
+    }
+
+    pub fn remove_dead_letter(&self, id: i64) -> Result<()> {
+        self.conn()
+            .execute("DELETE FROM dead_letters WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// A value remembered by a renderer, e.g. whether a workflow was failing.
+    pub fn state(&self, key: &str) -> Result<Option<String>> {
+        Ok(self
+            .conn()
+            .query_row(
+                "SELECT value FROM state WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    pub fn set_state(&self, key: &str, value: &str) -> Result<()> {
+        self.conn().execute(SET_STATE, params![key, value, now()])?;
+        Ok(())
+    }
+
+    pub fn set_states(&self, state: &[(String, String)]) -> Result<()> {
+        for (key, value) in state {
+            self.set_state(key, value)?;
+        }
+        Ok(())
+    }
+
+    /// Delivery ids seen after `cutoff`, oldest first.
+    pub fn recent_deliveries(&self, cutoff: i64) -> Result<Vec<(String, i64)>> {
+        let rows = self
+            .conn()
+            .prepare("SELECT id, seen_at FROM deliveries WHERE seen_at > ?1 ORDER BY seen_at")?
+            .query_map(params![cutoff], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(rows)
+    }
+
+    /// Records a delivery id and drops any that were seen before `cutoff`.
+    pub fn record_delivery(&self, id: &str, seen_at: i64, cutoff: i64) -> Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "INSERT OR REPLACE INTO deliveries (id, seen_at) VALUES (?1, ?2)",
+            params![id, seen_at],
+        )?;
+        conn.execute(
+            "DELETE FROM deliveries WHERE seen_at <= ?1",
+            params![cutoff],
+        )?;
+        drop(conn);
+        Ok(())
+    }
+
+    pub fn forget_delivery(&self, id: &str) -> Result<()> {
+        self.conn()
+            .execute("DELETE FROM deliveries WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+}
+
+const SET_STATE: &str = "INSERT OR REPLACE INTO state (key, value, updated_at) VALUES (?1, ?2, ?3)";
+
+const SELECT_DEAD_LETTER: &str =
+    "SELECT id, event_type, delivery_id, destination, error, created_at, payload FROM dead_letters";
+
+/// A dead letter with its payload still serialised, since rusqlite's row
+/// mapping can't fail with a json error.
+struct DeadLetterRow(DeadLetter, String);
+
+fn dead_letter_columns(row: &Row) -> rusqlite::Result<DeadLetterRow> {
+    Ok(DeadLetterRow(
+        DeadLetter {
+            id: row.get(0)?,
+            event_type: row.get(1)?,
+            delivery_id: row.get(2)?,
+            destination: row.get(3)?,
+            error: row.get(4)?,
+            created_at: row.get(5)?,
+            payload: serde_json::Value::Null,
+        },
+        row.get(6)?,
+    ))
+}
+
+impl DeadLetterRow {
+    fn parse(self) -> Result<DeadLetter> {
+        let Self(mut letter, payload) = self;
+        letter.payload = serde_json::from_str(&payload)?;
+        Ok(letter)
+    }
+}
+
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| i64::try_from(d.as_secs()).unwrap_or(i64::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{Source, Store, Tracking};
+
+    #[test]
+    fn test_jobs_are_taken_in_order_per_destination() {
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        let ids = store
+            .push_jobs(&["main", "bot"], &json!(1), None, None, &[])
+            .unwrap();
+        let tracking = Tracking::Remember("release:1".to_string());
+        store
+            .push_jobs(&["main"], &json!(3), Some(&tracking), None, &[])
+            .unwrap();
+
+        let job = store.next_job("main").unwrap().expect("main has jobs");
+        assert_eq!((job.id, job.message), (ids[0], json!(1)));
+
+        store.complete_job(ids[0]).unwrap();
+        let job = store.next_job("main").unwrap().expect("main has jobs");
+        assert_eq!((job.message, job.tracking), (json!(3), Some(tracking)));
+
+        store.complete_job(job.id).unwrap();
+        assert!(store.next_job("main").unwrap().is_none());
+        assert!(store.next_job("bot").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_failed_pushes_queue_nothing() {
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        store
+            .conn()
+            .execute_batch(
+                "CREATE TRIGGER reject_bot BEFORE INSERT ON jobs WHEN NEW.destination = 'bot'
+                 BEGIN SELECT RAISE(ABORT, 'rejected'); END",
+            )
+            .unwrap();
+
+        let state = [("star_milestone:rockdove".to_string(), "100".to_string())];
+        assert!(
+            store
+                .push_jobs(&["main", "bot"], &json!(1), None, None, &state)
+                .is_err()
+        );
+        assert!(store.next_job("main").unwrap().is_none());
+        assert!(store.state("star_milestone:rockdove").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_older_databases_are_migrated() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        // the jobs table as it was first added.
+        conn.execute_batch(
+            "CREATE TABLE jobs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                destination TEXT NOT NULL,
+                message TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+            INSERT INTO jobs (destination, message, created_at) VALUES ('main', '1', 0);",
+        )
+        .unwrap();
+        let store = Store::init(conn).expect("older databases open");
+
+        let job = store.next_job("main").unwrap().expect("old jobs are kept");
+        assert_eq!((job.message, job.tracking), (json!(1), None));
+        assert!(job.source.is_none());
+
+        let tracking = Tracking::Remember("release:1".to_string());
+        store
+            .push_jobs(&["main"], &json!(2), Some(&tracking), None, &[])
+            .unwrap();
+        store.complete_job(job.id).unwrap();
+        let job = store
+            .next_job("main")
+            .unwrap()
+            .expect("new jobs are queued");
+        assert_eq!(job.tracking, Some(tracking));
+    }
+
+    #[test]
+    fn test_dead_letters_keep_their_payload() {
+        let store = Store::open_in_memory().expect("in-memory store opens");
+        let source = Source {
+            event_type: "issues".to_string(),
+            delivery_id: Some("abc".to_string()),
+            payload: json!({ "action": "opened" }),
+        };
+        let id = store
+            .push_dead_letter(&source, Some("main"), "hook failed")
+            .unwrap();
+
+        let letter = store.dead_letter(id).unwrap().expect("dead letter exists");
+        assert_eq!(letter.payload, source.payload);
+        assert_eq!(letter.destination.as_deref(), Some("main"));
+        assert_eq!(store.dead_letters().unwrap().len(), 1);
+
+        store.remove_dead_letter(id).unwrap();
+        assert!(store.dead_letter(id).unwrap().is_none());
+    }
+}